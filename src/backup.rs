@@ -0,0 +1,241 @@
+use std::collections::BTreeSet;
+use std::fs::{create_dir_all, File, remove_dir_all, remove_file};
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::export::{entries, files, write_zip, Filter};
+use crate::import::extract_dir;
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::state::{data_dir, dir_name, now};
+
+/// A zip of an instance's `saves` directory
+#[derive(Debug, Clone)]
+pub struct Backup {
+  pub path: PathBuf,
+  pub created: u64,
+  /// What the backup was taken before, like `unlink` or `detach`
+  pub reason: String,
+}
+
+impl Backup {
+  fn read(path: PathBuf) -> Option<Backup> {
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let (created, reason) = stem.split_once('-')?;
+
+    Some(Backup {
+      created: created.parse().ok()?,
+      reason: reason.to_string(),
+      path,
+    })
+  }
+
+  /// File name without extension, what `backups` takes
+  pub fn id(&self) -> String {
+    self.path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+  }
+}
+
+/// Where backups of the instance called `name` are kept
+pub fn backups_dir(settings: &ApplicationSettings, name: &str) -> PathBuf {
+  settings.backups.directory.as_ref()
+    .map(PathBuf::from)
+    .unwrap_or_else(|| data_dir().join("backups"))
+    .join(dir_name(name))
+}
+
+/// Backups of the instance called `name`, oldest first
+pub fn list(settings: &ApplicationSettings, name: &str) -> Vec<Backup> {
+  let mut backups = backups_dir(settings, name)
+    .read_dir()
+    .map(|it| it.flatten()
+      .map(|it| it.path())
+      .filter(|it| it.extension().map(|it| it == "zip").unwrap_or(false))
+      .filter_map(Backup::read)
+      .collect::<Vec<_>>()
+    )
+    .unwrap_or_default();
+
+  backups.sort_by_key(|it| (it.created, it.id()));
+  backups
+}
+
+/// Zips the `saves` directory of `game_dir`. Returns `None` when backups are turned off or there are no
+/// saves, otherwise old backups of `name` beyond the configured count are removed afterwards
+pub fn backup_saves(settings: &ApplicationSettings, name: &str, game_dir: &Path, reason: &str) -> Result<Option<Backup>> {
+  if !settings.backups.enabled {
+    return Ok(None);
+  }
+
+  let backup = match write_backup(settings, name, game_dir, reason)? {
+    Some(backup) => backup,
+    None => return Ok(None),
+  };
+
+  let backups = list(settings, name);
+  let keep = settings.backups.keep;
+
+  if keep > 0 && backups.len() > keep {
+    for old in &backups[..backups.len() - keep] {
+      remove_file(&old.path)?;
+    }
+  }
+
+  Ok(Some(backup))
+}
+
+fn write_backup(settings: &ApplicationSettings, name: &str, game_dir: &Path, reason: &str) -> Result<Option<Backup>> {
+  let saves = game_dir.join("saves");
+
+  if !saves.is_dir() {
+    return Ok(None);
+  }
+
+  let saved = files(&saves, &Filter::default())?;
+
+  if saved.is_empty() {
+    return Ok(None);
+  }
+
+  let dir = backups_dir(settings, name);
+  let created = now();
+  let path = (0..)
+    .map(|it| match it {
+      0 => dir.join(format!("{}-{}.zip", created, reason)),
+      _ => dir.join(format!("{}-{}-{}.zip", created, reason, it)),
+    })
+    .find(|it| !it.exists())
+    .unwrap_or_default();
+
+  create_dir_all(&dir)?;
+  write_zip(&path, entries("saves/", &saves, &saved))?;
+
+  Ok(Some(Backup { path, created, reason: reason.to_string() }))
+}
+
+/// Finds a backup of `name` by its ID or path, the latest one when `query` is `None`
+pub fn find(settings: &ApplicationSettings, name: &str, query: Option<&str>) -> Result<Backup> {
+  if let Some(path) = query.map(Path::new).filter(|it| it.is_file()) {
+    return Ok(Backup::read(path.to_path_buf()).unwrap_or(Backup {
+      path: path.to_path_buf(),
+      created: 0,
+      reason: String::new(),
+    }));
+  }
+
+  let backups = list(settings, name);
+  let backup = match query {
+    Some(id) => backups.into_iter().find(|it| it.id() == id),
+    None => backups.into_iter().last(),
+  };
+
+  backup.ok_or_else(|| format!("No backup of \"{}\" found", name).into())
+}
+
+/// Restores the worlds in `backup` into `game_dir`. Worlds the backup has replace the current ones, the others
+/// are left alone. The current saves are backed up first, returns the number of files restored
+pub fn restore(settings: &ApplicationSettings, name: &str, backup: &Backup, game_dir: &Path) -> Result<usize> {
+  let mut zip = ZipArchive::new(File::open(&backup.path)?)?;
+  let saves = game_dir.join("saves");
+
+  // Not pruned, that could remove the backup being restored
+  write_backup(settings, name, game_dir, "restore")?;
+
+  let worlds = zip.file_names()
+    .filter_map(|it| it.strip_prefix("saves/")?.split('/').next())
+    .filter(|it| !it.is_empty() && *it != "." && *it != "..")
+    .map(str::to_string)
+    .collect::<BTreeSet<_>>();
+
+  for world in worlds {
+    let path = saves.join(world);
+
+    if path.is_dir() {
+      remove_dir_all(&path)?;
+    }
+  }
+
+  extract_dir(&mut zip, "saves", &saves)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::misc::BackupSettings;
+
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("backup-{}-{}", std::process::id(), name));
+    let _ = remove_dir_all(&dir);
+    create_dir_all(dir.join("game/saves/World/region")).unwrap();
+    std::fs::write(dir.join("game/saves/World/level.dat"), "level").unwrap();
+    std::fs::write(dir.join("game/saves/World/region/r.0.0.mca"), "region").unwrap();
+    dir
+  }
+
+  fn settings(dir: &Path, keep: usize) -> ApplicationSettings {
+    ApplicationSettings {
+      backups: BackupSettings { enabled: true, keep, directory: Some(dir.join("backups").to_string_lossy().to_string()) },
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn only_the_newest_backups_are_kept() {
+    let dir = temp_dir("keep");
+    let game_dir = dir.join("game");
+    let settings = settings(&dir, 2);
+
+    for reason in ["a", "b", "c", "d"] {
+      assert!(backup_saves(&settings, "Pack", &game_dir, reason).unwrap().is_some());
+    }
+
+    let reasons = list(&settings, "Pack").into_iter().map(|it| it.reason).collect::<Vec<_>>();
+
+    assert_eq!(reasons, ["c", "d"]);
+
+    let all = ApplicationSettings { backups: BackupSettings { keep: 0, ..settings.backups.clone() }, ..settings.clone() };
+
+    backup_saves(&all, "Pack", &game_dir, "e").unwrap();
+    backup_saves(&all, "Pack", &game_dir, "f").unwrap();
+
+    assert_eq!(list(&settings, "Pack").len(), 4);
+
+    let off = ApplicationSettings { backups: BackupSettings { enabled: false, ..settings.backups.clone() }, ..settings };
+
+    assert!(backup_saves(&off, "Pack", &game_dir, "g").unwrap().is_none());
+
+    remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn restore_replaces_backed_up_worlds_only() {
+    let dir = temp_dir("restore");
+    let game_dir = dir.join("game");
+    let settings = settings(&dir, 0);
+    let backup = backup_saves(&settings, "Pack", &game_dir, "unlink").unwrap().unwrap();
+
+    std::fs::write(game_dir.join("saves/World/level.dat"), "changed").unwrap();
+    std::fs::write(game_dir.join("saves/World/new.dat"), "new").unwrap();
+    create_dir_all(game_dir.join("saves/Other")).unwrap();
+    std::fs::write(game_dir.join("saves/Other/level.dat"), "other").unwrap();
+
+    let found = find(&settings, "Pack", Some(&backup.id())).unwrap();
+
+    assert_eq!(found.path, backup.path);
+    assert_eq!(restore(&settings, "Pack", &found, &game_dir).unwrap(), 2);
+    assert_eq!(std::fs::read_to_string(game_dir.join("saves/World/level.dat")).unwrap(), "level");
+    assert_eq!(std::fs::read_to_string(game_dir.join("saves/World/region/r.0.0.mca")).unwrap(), "region");
+    assert!(!game_dir.join("saves/World/new.dat").exists());
+    assert_eq!(std::fs::read_to_string(game_dir.join("saves/Other/level.dat")).unwrap(), "other");
+
+    // What was there before the restore is backed up too
+    let mut reasons = list(&settings, "Pack").into_iter().map(|it| it.reason).collect::<Vec<_>>();
+
+    reasons.sort();
+    assert_eq!(reasons, ["restore", "unlink"]);
+
+    remove_dir_all(dir).unwrap();
+  }
+}
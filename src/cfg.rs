@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// Flat `key=value` files as written by MultiMC and its forks (`multimc.cfg`, `instance.cfg`)
+pub type Cfg = BTreeMap<String, String>;
+
+pub fn parse_cfg(source: &str) -> Cfg {
+  source
+    .lines()
+    .map(str::trim)
+    .filter(|it| !it.is_empty() && !it.starts_with('[') && !it.starts_with('#'))
+    .filter_map(|it| it.split_once('='))
+    .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+    .collect()
+}
+
+pub fn read_cfg<P: AsRef<Path>>(path: P) -> std::io::Result<Cfg> {
+  Ok(parse_cfg(&read_to_string(path)?))
+}
+
+pub fn format_cfg(cfg: &Cfg) -> String {
+  cfg.iter().map(|(key, value)| format!("{}={}\n", key, value)).collect()
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::source::ModLoader;
+
+/// `manifest.json` at the root of a CurseForge modpack zip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+  pub minecraft: PackMinecraft,
+  #[serde(rename = "manifestType")] pub manifest_type: String,
+  #[serde(rename = "manifestVersion")] pub manifest_version: u32,
+  pub name: String,
+  #[serde(default)] pub version: String,
+  #[serde(default)] pub author: String,
+  #[serde(default)] pub files: Vec<PackFile>,
+  #[serde(default = "default_overrides")] pub overrides: String,
+}
+
+fn default_overrides() -> String {
+  String::from("overrides")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackMinecraft {
+  pub version: String,
+  #[serde(rename = "modLoaders", default)] pub mod_loaders: Vec<PackModLoader>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackModLoader {
+  /// `<loader>-<version>`, e.g. `forge-47.1.0`
+  pub id: String,
+  #[serde(default)] pub primary: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackFile {
+  #[serde(rename = "projectID")] pub project_id: u64,
+  #[serde(rename = "fileID")] pub file_id: u64,
+  #[serde(default = "default_required")] pub required: bool,
+}
+
+fn default_required() -> bool {
+  true
+}
+
+impl PackManifest {
+  pub fn loader(&self) -> ModLoader {
+    let loaders = &self.minecraft.mod_loaders;
+
+    loaders.iter()
+      .find(|it| it.primary)
+      .or_else(|| loaders.first())
+      .map(|it| ModLoader::from_id(&it.id))
+      .unwrap_or(ModLoader::Vanilla)
+  }
+}
@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use crate::cfg::{format_cfg, read_cfg};
+use crate::jar::{JarLoader, JarMod, read_mods_dir};
+use crate::launcher::TargetLauncher;
+use crate::link::Result;
+use crate::source::{ModLoader, SourceInstance};
+use crate::version::matches_range;
+
+/// Something that will likely stop an instance from starting
+#[derive(Debug, Clone)]
+pub enum Problem {
+  /// A required dependency isn't installed, or not in a version the mod accepts
+  UnmetDependency { jar: String, id: String, dependency: String, versions: String, installed: Option<String> },
+  /// None of the jar's metadata is for the instance's loader
+  WrongLoader { jar: String, loaders: Vec<JarLoader> },
+  IncompatibleMinecraft { jar: String, id: String, versions: String },
+  /// Several jars declare the same mod id
+  DuplicateId { id: String, jars: Vec<String> },
+}
+
+impl std::fmt::Display for Problem {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Problem::UnmetDependency { jar, id, dependency, versions, installed: None } => {
+        write!(f, "{} ({}) requires {} {}, which isn't installed", id, jar, dependency, versions)
+      }
+      Problem::UnmetDependency { jar, id, dependency, versions, installed: Some(installed) } => {
+        write!(f, "{} ({}) requires {} {}, but {} is installed", id, jar, dependency, versions, installed)
+      }
+      Problem::WrongLoader { jar, loaders } => {
+        let loaders = loaders.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        write!(f, "{} is a {} mod", jar, loaders.join("/"))
+      }
+      Problem::IncompatibleMinecraft { jar, id, versions } => {
+        write!(f, "{} ({}) only supports Minecraft {}", id, jar, versions)
+      }
+      Problem::DuplicateId { id, jars } => {
+        write!(f, "{} is installed more than once: {}", id, jars.join(", "))
+      }
+    }
+  }
+}
+
+/// Whether an instance running `loader` loads mods written for `jar`
+fn accepts(loader: &ModLoader, jar: JarLoader) -> bool {
+  match loader {
+    ModLoader::Forge(_) => jar == JarLoader::Forge,
+    ModLoader::NeoForge(_) => matches!(jar, JarLoader::NeoForge | JarLoader::Forge),
+    ModLoader::Fabric(_) => jar == JarLoader::Fabric,
+    ModLoader::Quilt(_) => matches!(jar, JarLoader::Quilt | JarLoader::Fabric),
+    ModLoader::Vanilla => false,
+  }
+}
+
+/// Version of something the game or loader provides rather than a mod, `Some("")` when it can't be told
+fn builtin_version(instance: &SourceInstance, id: &str) -> Option<String> {
+  let loader = |matches: bool| match matches {
+    true => instance.loader.version().map(str::to_string),
+    false => Some(String::new()),
+  };
+
+  match id.to_lowercase().as_str() {
+    "minecraft" => Some(instance.mc_version.clone()),
+    "forge" => loader(matches!(instance.loader, ModLoader::Forge(_))),
+    "neoforge" => loader(matches!(instance.loader, ModLoader::NeoForge(_))),
+    "fabricloader" => loader(matches!(instance.loader, ModLoader::Fabric(_))),
+    "quilt_loader" => loader(matches!(instance.loader, ModLoader::Quilt(_))),
+    "java" | "fml" | "javafml" | "mcp" => Some(String::new()),
+    _ => None,
+  }
+}
+
+/// Checks the jars in the `mods` folder of `instance` against each other and against its loader and Minecraft version
+pub fn check(instance: &SourceInstance) -> Vec<Problem> {
+  let jars = read_mods_dir(&instance.game_dir.join("mods"));
+  let mut problems = Vec::new();
+  let mut loaded = Vec::<(String, JarMod)>::new();
+
+  for jar in jars {
+    let name = jar.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    if jar.mods.is_empty() {
+      continue;
+    }
+
+    let (usable, other) = jar.mods.into_iter().partition::<Vec<_>, _>(|it| accepts(&instance.loader, it.loader));
+
+    if usable.is_empty() {
+      let mut loaders = other.iter().map(|it| it.loader).collect::<Vec<_>>();
+      loaders.dedup();
+      problems.push(Problem::WrongLoader { jar: name, loaders });
+      continue;
+    }
+
+    loaded.extend(usable.into_iter().map(|it| (name.clone(), it)));
+  }
+
+  let mut jars_by_id = BTreeMap::<&str, Vec<String>>::new();
+
+  for (jar, jar_mod) in &loaded {
+    let jars = jars_by_id.entry(jar_mod.id.as_str()).or_default();
+
+    if !jars.contains(jar) {
+      jars.push(jar.clone());
+    }
+  }
+
+  let installed_version = |id: &str| {
+    loaded.iter()
+      .find(|(_, it)| it.id == id)
+      .map(|(_, it)| it.version.clone())
+      .or_else(|| loaded.iter().any(|(_, it)| it.provides.iter().any(|it| it == id)).then(String::new))
+  };
+
+  for (jar, jar_mod) in &loaded {
+    let maven = matches!(jar_mod.loader, JarLoader::Forge | JarLoader::NeoForge);
+
+    for dependency in jar_mod.dependencies.iter().filter(|it| it.required) {
+      let installed = builtin_version(instance, &dependency.id).or_else(|| installed_version(&dependency.id));
+      // Unresolved placeholders and provided ids carry no usable version
+      let comparable = installed.as_ref().filter(|it| !it.is_empty() && !it.starts_with('$'));
+
+      if let Some(version) = comparable {
+        if matches_range(version, &dependency.versions, maven) {
+          continue;
+        }
+      }
+
+      if installed.is_some() && comparable.is_none() {
+        continue;
+      }
+
+      if dependency.id == "minecraft" {
+        problems.push(Problem::IncompatibleMinecraft {
+          jar: jar.clone(),
+          id: jar_mod.id.clone(),
+          versions: dependency.versions.clone(),
+        });
+        continue;
+      }
+
+      problems.push(Problem::UnmetDependency {
+        jar: jar.clone(),
+        id: jar_mod.id.clone(),
+        dependency: dependency.id.clone(),
+        versions: dependency.versions.clone(),
+        installed,
+      });
+    }
+  }
+
+  for (id, jars) in jars_by_id {
+    if jars.len() > 1 {
+      problems.push(Problem::DuplicateId { id: id.to_string(), jars });
+    }
+  }
+
+  problems
+}
+
+/// What tells our pre-launch command apart from the user's own
+const HOOK_ARGS: &str = " check --mmc \"$INST_DIR\"";
+
+/// Launcher commands that only apply to an instance with `OverrideCommands`, copied from the launcher's
+/// config so hooking an instance doesn't drop wrappers like `gamemoderun` or `prime-run`
+const COMMAND_KEYS: [&str; 3] = ["PreLaunchCommand", "WrapperCommand", "PostExitCommand"];
+
+/// Command MultiMC runs before launching, a failing check stops the launch. `then` is the pre-launch command
+/// the instance had before, `check` runs it after passing
+pub fn pre_launch_command(then: Option<&str>) -> Result<String> {
+  let exe = std::env::current_exe()?;
+  let mut command = format!("\"{}\"{}", exe.display(), HOOK_ARGS);
+
+  if let Some(then) = then {
+    command.push_str(&format!(" --then \"{}\"", then.replace('\\', "\\\\").replace('"', "\\\"")));
+  }
+
+  Ok(command)
+}
+
+/// The user's own part of `command`, the `--then` part when it is our hook
+fn chained_command(command: &str) -> Option<String> {
+  let command = match command.contains(HOOK_ARGS) {
+    true => {
+      let mut chars = command.split_once(" --then \"")?.1.strip_suffix('"')?.chars();
+      let mut unescaped = String::new();
+
+      while let Some(c) = chars.next() {
+        unescaped.push(if c == '\\' { chars.next()? } else { c });
+      }
+
+      unescaped
+    }
+    false => command.to_string(),
+  };
+
+  Some(command).filter(|it| !it.trim().is_empty())
+}
+
+/// Makes the MultiMC instance at `mmc_path` run `check` before every launch. This needs `OverrideCommands`, so
+/// the launcher's global commands are copied into the instance first unless it already overrides them, and a
+/// pre-launch command that was already set is chained after the check
+pub fn install_hook(launcher: &dyn TargetLauncher, mmc_path: &Path) -> Result<()> {
+  let path = mmc_path.join("instance.cfg");
+  let mut cfg = read_cfg(&path)?;
+
+  if cfg.get("OverrideCommands").map(String::as_str) != Some("true") {
+    let global = mmc_path.parent()
+      .map(|it| launcher.data_dir(it))
+      .and_then(|it| read_cfg(it.join(launcher.config_file())).ok())
+      .unwrap_or_default();
+
+    for key in COMMAND_KEYS {
+      match global.get(key).filter(|it| !it.trim().is_empty()) {
+        Some(command) => cfg.insert(key.to_string(), command.clone()),
+        None => cfg.remove(key),
+      };
+    }
+  }
+
+  let then = cfg.get("PreLaunchCommand").and_then(|it| chained_command(it));
+
+  cfg.insert(String::from("OverrideCommands"), String::from("true"));
+  cfg.insert(String::from("PreLaunchCommand"), pre_launch_command(then.as_deref())?);
+
+  File::create(&path)?.write_all(format_cfg(&cfg).as_bytes())?;
+
+  Ok(())
+}
+
+/// Runs the pre-launch command `check` was chained in front of, through the shell like the launcher would
+pub fn run_chained(command: &str) -> Result<()> {
+  #[cfg(windows)] let status = std::process::Command::new("cmd").arg("/C").arg(command).status()?;
+  #[cfg(not(windows))] let status = std::process::Command::new("sh").arg("-c").arg(command).status()?;
+
+  match status.success() {
+    true => Ok(()),
+    false => Err(format!("Pre-launch command {} exited with {}", command, status).into()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use zip::write::FileOptions;
+  use zip::ZipWriter;
+
+  use super::*;
+
+  /// Fabric 1.20.1 instance with a `mods` folder holding a jar for each `(file, fabric.mod.json)`
+  fn instance(name: &str, jars: &[(&str, &str)]) -> SourceInstance {
+    let dir = std::env::temp_dir().join(format!("check-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("mods")).unwrap();
+
+    for (file, json) in jars {
+      let mut zip = ZipWriter::new(File::create(dir.join("mods").join(file)).unwrap());
+      let (entry, json) = match json.strip_prefix("forge:") {
+        Some(toml) => ("META-INF/mods.toml", toml),
+        None => ("fabric.mod.json", *json),
+      };
+
+      zip.start_file(entry, FileOptions::default()).unwrap();
+      zip.write_all(json.as_bytes()).unwrap();
+      zip.finish().unwrap();
+    }
+
+    SourceInstance {
+      name: name.to_string(),
+      mc_version: String::from("1.20.1"),
+      loader: ModLoader::Fabric(String::from("0.14.21")),
+      game_dir: dir,
+      icon: None,
+      managed: None,
+    }
+  }
+
+  fn finish(instance: SourceInstance) -> Vec<Problem> {
+    let problems = check(&instance);
+
+    std::fs::remove_dir_all(&instance.game_dir).unwrap();
+    problems
+  }
+
+  #[test]
+  fn satisfied_dependencies_are_fine() {
+    let problems = finish(instance("fine", &[
+      ("api.jar", r#"{ "id": "fabric-api", "version": "0.86.1", "provides": ["fabric"] }"#),
+      ("sodium.jar", r#"{ "id": "sodium", "version": "0.5.3", "depends": {
+        "fabricloader": ">=0.12", "minecraft": "1.20.x", "fabric": "*", "fabric-api": "^0.86"
+      } }"#),
+    ]));
+
+    assert!(problems.is_empty(), "{:?}", problems);
+  }
+
+  #[test]
+  fn unmet_dependencies() {
+    let problems = finish(instance("unmet", &[
+      ("api.jar", r#"{ "id": "fabric-api", "version": "0.5.0" }"#),
+      ("a.jar", r#"{ "id": "a", "version": "1", "depends": { "cloth-config": "*", "fabric-api": "^0.4" } }"#),
+    ]));
+
+    let problems = problems.iter()
+      .map(|it| match it {
+        Problem::UnmetDependency { jar, id, dependency, installed, .. } => (jar, id, dependency, installed.clone()),
+        other => panic!("unexpected {:?}", other),
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(problems.len(), 2);
+    assert!(problems.contains(&(&String::from("a.jar"), &String::from("a"), &String::from("cloth-config"), None)));
+    assert!(problems.contains(&(
+      &String::from("a.jar"), &String::from("a"), &String::from("fabric-api"), Some(String::from("0.5.0"))
+    )));
+  }
+
+  #[test]
+  fn wrong_loader() {
+    let problems = finish(instance("loader", &[
+      ("forge.jar", "forge:[[mods]]\nmodId = \"jei\"\nversion = \"15\"\n"),
+    ]));
+
+    assert!(matches!(
+      problems.as_slice(),
+      [Problem::WrongLoader { jar, loaders }] if jar == "forge.jar" && loaders == &[JarLoader::Forge]
+    ), "{:?}", problems);
+  }
+
+  #[test]
+  fn incompatible_minecraft() {
+    let problems = finish(instance("minecraft", &[
+      ("old.jar", r#"{ "id": "old", "version": "1", "depends": { "minecraft": "~1.19.2" } }"#),
+    ]));
+
+    assert!(matches!(
+      problems.as_slice(),
+      [Problem::IncompatibleMinecraft { jar, id, versions }] if jar == "old.jar" && id == "old" && versions == "~1.19.2"
+    ), "{:?}", problems);
+  }
+
+  #[test]
+  fn duplicate_ids() {
+    let problems = finish(instance("duplicate", &[
+      ("sodium-0.5.2.jar", r#"{ "id": "sodium", "version": "0.5.2" }"#),
+      ("sodium-0.5.3.jar", r#"{ "id": "sodium", "version": "0.5.3" }"#),
+    ]));
+
+    assert!(matches!(
+      problems.as_slice(),
+      [Problem::DuplicateId { id, jars }] if id == "sodium" && jars == &["sodium-0.5.2.jar", "sodium-0.5.3.jar"]
+    ), "{:?}", problems);
+  }
+}
@@ -0,0 +1,788 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use crate::detach::{Progress, Stage};
+use crate::verify::FileStatus;
+use crate::dedupe::format_size;
+use crate::export::{ExportFormat, Filter};
+use crate::launcher::game_dir;
+use crate::link::Result;
+use crate::logs::CrashLogs;
+use crate::misc::{ApplicationSettings, Flags};
+use crate::modpack::ModPack;
+use crate::mrpack::{ModrinthApi, ModrinthResolver, Offline};
+use crate::report::{Report, ReportFormat};
+use crate::reverse::MMCInstance;
+use crate::snapshot::Store;
+use crate::state::InstanceState;
+
+pub fn subcommands<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+  app
+    .subcommand(
+      SubCommand::with_name("detach")
+        .about("Replaces a linked instance's symlink with a verified copy, so it stops receiving updates")
+        .arg(instance_arg())
+    )
+    .subcommand(
+      SubCommand::with_name("import")
+        .about("Creates a new instance from a CurseForge modpack zip")
+        .arg(Arg::with_name("zip").required(true).help("Modpack zip containing manifest.json"))
+        .arg(
+          Arg::with_name("name")
+            .long("name")
+            .takes_value(true)
+            .help("Instance name, defaults to the pack's name")
+        )
+        .arg(
+          Arg::with_name("cache")
+            .long("cache")
+            .value_name("DIR")
+            .takes_value(true)
+            .help("Directory to take mod files from, overrides the mod_cache setting")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("mods")
+        .about("Lists the mods of an instance, flagging ones CurseForge doesn't know about")
+        .arg(instance_arg())
+    )
+    .subcommand(
+      SubCommand::with_name("check")
+        .about("Looks for missing dependencies, mods for the wrong loader or Minecraft version and duplicate mods")
+        .arg(instance_arg().required_unless("mmc"))
+        .arg(
+          Arg::with_name("mmc")
+            .long("mmc")
+            .value_name("DIR")
+            .takes_value(true)
+            .help("Check a MultiMC instance directory instead, this is what the pre-launch command uses")
+        )
+        .arg(
+          Arg::with_name("hook")
+            .long("hook")
+            .conflicts_with("mmc")
+            .help(
+              "Makes the linked instance run this check before every launch, the launcher's global commands are \
+              copied into the instance and an existing pre-launch command runs after the check"
+            )
+        )
+        .arg(
+          Arg::with_name("then")
+            .long("then")
+            .value_name("COMMAND")
+            .takes_value(true)
+            .requires("mmc")
+            .help("Runs COMMAND through the shell after the check passes, the hook uses this to keep the instance's own pre-launch command")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("verify")
+        .about("Compares mod files with the fingerprints CurseForge recorded when installing them")
+        .arg(instance_arg())
+        .arg(
+          Arg::with_name("json")
+            .long("json")
+            .help("Print the results as JSON")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("report")
+        .about("Renders the mod list of an instance as Markdown, HTML, CSV or a custom template")
+        .arg(instance_arg())
+        .arg(
+          Arg::with_name("format")
+            .long("format")
+            .takes_value(true)
+            .possible_values(&ReportFormat::NAMES)
+            .default_value("markdown")
+        )
+        .arg(
+          Arg::with_name("template")
+            .long("template")
+            .value_name("FILE")
+            .takes_value(true)
+            .help(
+              "Template to render instead. {{name}}, {{minecraft}}, {{loader}} and {{count}} describe the \
+              instance, {{#mods}}...{{/mods}} is repeated for every mod with {{name}}, {{version}}, {{url}}, \
+              {{file}} and {{side}}"
+            )
+        )
+        .arg(
+          Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Where to write the report, printed when not given")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("snapshot")
+        .about("Saves the mods, configs and instance file of an instance, so they can be rolled back later")
+        .arg(instance_arg())
+        .arg(
+          Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Where to save the snapshot, defaults to the application's data directory")
+        )
+        .arg(
+          Arg::with_name("list")
+            .long("list")
+            .conflicts_with("output")
+            .help("List the saved snapshots instead")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("rollback")
+        .about("Restores the mods, configs and instance file of a snapshot, the current state is snapshotted first")
+        .arg(instance_arg())
+        .arg(Arg::with_name("snapshot").help("Snapshot ID or file, defaults to the latest snapshot"))
+    )
+    .subcommand(
+      SubCommand::with_name("backups")
+        .about("Lists or restores the save backups taken before unlinking, detaching and similar operations")
+        .arg(instance_arg())
+        .arg(Arg::with_name("backup").help("Backup ID or file to restore, defaults to the latest backup"))
+        .arg(
+          Arg::with_name("restore")
+            .long("restore")
+            .help("Restore a backup instead of listing them, the current saves are backed up first")
+        )
+        .arg(
+          Arg::with_name("to")
+            .long("to")
+            .value_name("DIR")
+            .takes_value(true)
+            .requires("restore")
+            .help("Game directory to restore into, defaults to the linked instance's or the source instance's")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("dedupe")
+        .about("Replaces identical mods, resource packs and shader packs across all instances with hardlinks")
+        .arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Only report what would be linked and how much space that saves")
+        )
+        .arg(
+          Arg::with_name("undo")
+            .long("undo")
+            .conflicts_with("dry-run")
+            .help("Turn the hardlinks made by earlier runs back into separate files")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("sync-options")
+        .about("Merges the template options.txt, optionsof.txt and optionsshaders.txt into every linked instance")
+        .arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Only list the keys that would change")
+        )
+        .arg(
+          Arg::with_name("save-template")
+            .long("save-template")
+            .value_name("INSTANCE")
+            .takes_value(true)
+            .conflicts_with("dry-run")
+            .help("Replace the template with the options files of an instance instead")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("sync-servers")
+        .about("Adds the servers from the settings to the server list of every linked instance")
+        .arg(
+          Arg::with_name("pin")
+            .long("pin")
+            .help("Move them to the top of the list, overrides the pin setting")
+        )
+        .arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Only list the servers that would be added")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("logs")
+        .about("Summarizes the newest crash report and log of an instance and zips them up for an issue")
+        .arg(instance_arg())
+        .arg(
+          Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Where to write the zip, defaults to <instance>-logs.zip")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("launch")
+        .about("Starts a linked instance in the target launcher")
+        .arg(instance_arg())
+    )
+    .subcommand(
+      SubCommand::with_name("shortcuts")
+        .about("Regenerates the desktop entries of all linked instances (Linux only)")
+    )
+    .subcommand(
+      SubCommand::with_name("watch")
+        .about("Watches linked instances for updates from the source launcher")
+        .arg(
+          Arg::with_name("snapshot")
+            .long("snapshot")
+            .help("Snapshot instances when they change, so a bad update can be rolled back")
+        )
+        .arg(
+          Arg::with_name("interval")
+            .long("interval")
+            .value_name("SECONDS")
+            .takes_value(true)
+            .default_value("10")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("diff")
+        .about("Shows what changed between two instances, or between an instance and a snapshot")
+        .arg(Arg::with_name("a").required(true).help("Instance, snapshot file or <instance>@<snapshot ID>"))
+        .arg(Arg::with_name("b").required(true).help("Instance, snapshot file or <instance>@<snapshot ID>"))
+        .arg(
+          Arg::with_name("json")
+            .long("json")
+            .help("Print the differences as JSON")
+        )
+    )
+    .subcommand(
+      SubCommand::with_name("export")
+        .about("Packs an instance into a modpack zip")
+        .arg(instance_arg())
+        .arg(
+          Arg::with_name("format")
+            .long("format")
+            .takes_value(true)
+            .possible_values(&ExportFormat::NAMES)
+            .default_value("curseforge")
+        )
+        .arg(
+          Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Where to write the pack, defaults to <instance name>.zip or .mrpack")
+        )
+        .arg(
+          Arg::with_name("include")
+            .long("include")
+            .value_name("PATTERN")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Pack files matching this pattern even when excluded, e.g. saves/MyWorld")
+        )
+        .arg(
+          Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("PATTERN")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Leave out files matching this pattern, saves, logs and screenshots are always left out")
+        )
+        .arg(
+          Arg::with_name("offline")
+            .long("offline")
+            .help("Don't ask Modrinth where mods are hosted for .mrpack, mods it can't find locally get bundled")
+        )
+    )
+}
+
+fn instance_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("instance")
+    .required(true)
+    .help("Instance name, directory name or path")
+}
+
+/// Runs the subcommand given on the command line, `None` when the window should open instead
+pub fn run(flags: &Flags) -> Option<i32> {
+  let (name, matches) = flags.command.as_ref()?;
+  let settings = flags.load_settings();
+
+  let result = match name.as_str() {
+    "detach" => detach(&settings, matches),
+    "import" => import(&settings, matches),
+    "export" => export(&settings, matches),
+    "mods" => mods(&settings, matches),
+    "check" => check(&settings, matches),
+    "verify" => verify(&settings, matches),
+    "report" => report(&settings, matches),
+    "snapshot" => snapshot(&settings, matches),
+    "diff" => diff(&settings, matches),
+    "rollback" => rollback(&settings, matches),
+    "watch" => watch(&settings, matches),
+    "backups" => backups(&settings, matches),
+    "dedupe" => dedupe(&settings, matches),
+    "sync-options" => sync_options(&settings, matches),
+    "sync-servers" => sync_servers(&settings, matches),
+    "logs" => logs(&settings, matches),
+    "launch" => launch(&settings, matches),
+    "shortcuts" => shortcuts(&settings),
+    _ => Ok(()),
+  };
+
+  match result {
+    Ok(_) => Some(0),
+    Err(err) => {
+      eprintln!("error: {}", err);
+      Some(1)
+    }
+  }
+}
+
+fn find_modpack(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<ModPack> {
+  let query = matches.value_of("instance").unwrap_or_default();
+
+  ModPack::find(settings.cf_dir(), settings.source, query)
+    .ok_or_else(|| format!("No {} instance called \"{}\"", settings.source, query).into())
+}
+
+fn print_progress(progress: Progress) {
+  let stage = match progress.stage {
+    Stage::Copying => "Copying",
+    Stage::Verifying => "Verifying",
+  };
+
+  let percent = match progress.total {
+    0 => 100,
+    total => progress.done * 100 / total,
+  };
+
+  eprint!("\r{} {:>3}%", stage, percent);
+  let _ = std::io::stderr().flush();
+
+  if progress.done == progress.total {
+    eprintln!();
+  }
+}
+
+fn detach(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let copied = crate::detach::detach(settings, &settings.mmc_dir(), &mp, &mut print_progress)?;
+
+  println!("Detached \"{}\", copied {} files", mp, copied);
+
+  Ok(())
+}
+
+fn import(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let zip = Path::new(matches.value_of("zip").unwrap_or_default());
+  let cache = matches.value_of("cache").or(settings.mod_cache.as_deref());
+  let fetcher = crate::import::fetcher(cache);
+  let report = crate::import::import(settings, &settings.mmc_dir(), zip, matches.value_of("name"), fetcher.as_ref())?;
+
+  println!(
+    "Imported \"{}\" into {}, {} override files, {} mods",
+    report.name, report.path.display(), report.extracted, report.fetched
+  );
+
+  for file in &report.missing {
+    println!("missing: project {} file {}", file.project_id, file.file_id);
+  }
+
+  Ok(())
+}
+
+fn values(matches: &ArgMatches, name: &str) -> Vec<String> {
+  matches.values_of(name).map(|it| it.map(str::to_string).collect()).unwrap_or_default()
+}
+
+fn export(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let filter = Filter::new(values(matches, "include"), values(matches, "exclude"));
+  let format = ExportFormat::from_name(matches.value_of("format").unwrap_or_default())
+    .ok_or("Unknown export format")?;
+  let output = matches.value_of("output")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from(format!("{}.{}", mp, format.extension())));
+
+  let report = match format {
+    ExportFormat::CurseForge => crate::export::export_curseforge(&mp, &output, &filter)?,
+    ExportFormat::Mrpack => {
+      let resolver: &dyn ModrinthResolver = match matches.is_present("offline") {
+        true => &Offline,
+        false => &ModrinthApi,
+      };
+
+      crate::export::export_mrpack(&mp, &output, &filter, resolver)?
+    }
+    ExportFormat::MultiMC => crate::export::export_mmc(settings, &settings.mmc_dir(), &mp, &output, &filter)?,
+  };
+
+  for warning in &report.warnings {
+    eprintln!("warning: {}", warning);
+  }
+
+  println!(
+    "Exported \"{}\" to {}, {} files referenced, {} files packed",
+    mp, report.path.display(), report.referenced, report.packed
+  );
+
+  Ok(())
+}
+
+fn mods(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let entries = crate::inventory::inventory(&mp)?;
+
+  for entry in &entries {
+    println!("{}", entry);
+
+    if let (Some(project_id), Some(file_id)) = (entry.project_id, entry.file_id) {
+      println!("  project {} file {}", project_id, file_id);
+    }
+
+    for jar_mod in &entry.mods {
+      println!("  {} {} {} ({})", jar_mod.loader, jar_mod.id, jar_mod.version, jar_mod.name);
+    }
+
+    if !entry.dependencies.is_empty() {
+      println!("  requires {}", entry.dependencies.join(", "));
+    }
+  }
+
+  println!(
+    "{} mods, {} manually added",
+    entries.len(), entries.iter().filter(|it| it.manual).count()
+  );
+
+  Ok(())
+}
+
+fn check(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let instance = match matches.value_of("mmc") {
+    Some(dir) => MMCInstance { dir: PathBuf::from(dir), name: String::new() }.read()?,
+    None => {
+      let mp = find_modpack(settings, matches)?;
+
+      if matches.is_present("hook") {
+        let mmc_path = mp.mmc_path(&settings.mmc_dir()).ok_or("This instance isn't linked")?;
+
+        crate::check::install_hook(settings.target.launcher(), &mmc_path)?;
+        println!("\"{}\" now runs this check before launching", mp);
+      }
+
+      mp.instance()?
+    }
+  };
+
+  let problems = crate::check::check(&instance);
+
+  for problem in &problems {
+    println!("{}", problem);
+  }
+
+  match problems.len() {
+    0 => println!("No problems found"),
+    count => return Err(format!("{} problems found", count).into()),
+  }
+
+  match matches.value_of("then") {
+    Some(command) => crate::check::run_chained(command),
+    None => Ok(()),
+  }
+}
+
+fn verify(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let checks = crate::verify::verify(&mp)?;
+  let failed = checks.iter().filter(|it| it.status.is_problem()).count();
+
+  if matches.is_present("json") {
+    println!("{}", serde_json::to_string_pretty(&checks)?);
+  } else {
+    for check in checks.iter().filter(|it| it.status != FileStatus::Ok) {
+      println!("{:?}: {}", check.status, check.path);
+    }
+
+    println!("{} files checked, {} problems", checks.len(), failed);
+  }
+
+  match failed {
+    0 => Ok(()),
+    // Details were printed already, the exit code is what matters in CI
+    _ => Err(format!("{} files don't match", failed).into()),
+  }
+}
+
+fn report(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let report = Report::new(&mp)?;
+
+  let rendered = match matches.value_of("template") {
+    Some(template) => report.render_template(&std::fs::read_to_string(template)?),
+    None => {
+      let format = ReportFormat::from_name(matches.value_of("format").unwrap_or_default())
+        .ok_or("Unknown report format")?;
+
+      report.render(format)
+    }
+  };
+
+  match matches.value_of("output") {
+    Some(output) => std::fs::write(output, rendered)?,
+    None => print!("{}", rendered),
+  }
+
+  Ok(())
+}
+
+fn snapshot(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+
+  if matches.is_present("list") {
+    for snapshot in crate::snapshot::list(&mp.instance()?.name) {
+      println!(
+        "{}  {}  {} mods",
+        snapshot.id(), crate::snapshot::format_time(snapshot.state.created), snapshot.state.mods.len()
+      );
+    }
+
+    return Ok(());
+  }
+
+  let output = matches.value_of("output").map(Path::new);
+  let snapshot = crate::snapshot::take(&mp, &Store::default(), output, true)?
+    .ok_or("Snapshot wasn't saved")?;
+
+  println!("Saved snapshot {} of \"{}\" to {}", snapshot.id(), mp, snapshot.path.display());
+
+  Ok(())
+}
+
+fn rollback(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let snapshot = crate::snapshot::find(&mp.instance()?.name, matches.value_of("snapshot"))?;
+  let (restored, removed) = crate::snapshot::rollback(&mp, &Store::default(), &snapshot)?;
+
+  println!(
+    "Rolled \"{}\" back to {}, {} files restored, {} removed",
+    mp, crate::snapshot::format_time(snapshot.state.created), restored, removed
+  );
+
+  Ok(())
+}
+
+fn backups(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let instance = mp.instance()?;
+
+  if !matches.is_present("restore") {
+    for backup in crate::backup::list(settings, &instance.name) {
+      println!("{}  {}  {}", backup.id(), crate::snapshot::format_time(backup.created), backup.reason);
+    }
+
+    return Ok(());
+  }
+
+  let backup = crate::backup::find(settings, &instance.name, matches.value_of("backup"))?;
+  let game_dir = match matches.value_of("to") {
+    Some(dir) => PathBuf::from(dir),
+    None => mp.mmc_path(&settings.mmc_dir())
+      .filter(|it| it.exists())
+      .map(|it| game_dir(&it))
+      .unwrap_or(instance.game_dir),
+  };
+
+  let restored = crate::backup::restore(settings, &instance.name, &backup, &game_dir)?;
+
+  println!("Restored {} files from {} into {}", restored, backup.id(), game_dir.join("saves").display());
+
+  Ok(())
+}
+
+fn dedupe(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  if matches.is_present("undo") {
+    let report = crate::dedupe::undo(&crate::dedupe::journals_dir())?;
+
+    for (path, reason) in &report.failed {
+      eprintln!("failed {}: {}", path.display(), reason);
+    }
+
+    println!("Restored {} files to separate copies", report.restored);
+
+    return match report.failed.len() {
+      0 => Ok(()),
+      failed => Err(format!("{} files are still linked, run --undo again to retry", failed).into()),
+    };
+  }
+
+  let duplicates = crate::dedupe::find_duplicates(settings)?;
+  let savings = duplicates.iter().map(|it| it.savings).sum::<u64>();
+
+  if matches.is_present("dry-run") {
+    for group in &duplicates {
+      println!("{} ({}, {} copies)", group.original.display(), format_size(group.size), group.copies.len());
+
+      for copy in &group.copies {
+        println!("  {}", copy.display());
+      }
+    }
+
+    println!("Linking would save {}", format_size(savings));
+    return Ok(());
+  }
+
+  let report = crate::dedupe::dedupe(&duplicates, &crate::dedupe::journals_dir())?;
+
+  for (path, reason) in &report.skipped {
+    eprintln!("skipped {}: {}", path.display(), reason);
+  }
+
+  println!("Linked {} files, saved {}", report.linked, format_size(report.saved));
+
+  Ok(())
+}
+
+fn sync_options(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let template_dir = crate::options::template_dir(settings);
+
+  if let Some(query) = matches.value_of("save-template") {
+    let mp = ModPack::find(settings.cf_dir(), settings.source, query)
+      .ok_or_else(|| format!("No {} instance called \"{}\"", settings.source, query))?;
+    let saved = crate::options::save_template(settings, &mp.instance()?.game_dir)?;
+
+    println!("Saved {} options files of \"{}\" to {}", saved, mp, template_dir.display());
+    return Ok(());
+  }
+
+  if !template_dir.is_dir() {
+    return Err(format!("No template found, put options files into {} or use --save-template", template_dir.display()).into());
+  }
+
+  let dry_run = matches.is_present("dry-run");
+
+  for (mp, game_dir) in crate::options::linked_instances(settings) {
+    let changes = crate::options::apply(settings, &game_dir, &mp.instance()?.mc_version, dry_run)?;
+
+    if changes.is_empty() {
+      println!("\"{}\" is up to date", mp);
+    }
+
+    for (file, keys) in changes {
+      println!("\"{}\" {}: {}", mp, file, keys.join(", "));
+    }
+  }
+
+  Ok(())
+}
+
+fn sync_servers(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let servers = &settings.servers.list;
+
+  if servers.is_empty() {
+    return Err(Box::from("No servers configured, add them to the settings as [[servers.list]] with a name and ip"));
+  }
+
+  let pin = settings.servers.pin || matches.is_present("pin");
+  let dry_run = matches.is_present("dry-run");
+
+  for (mp, game_dir) in crate::options::linked_instances(settings) {
+    let report = crate::servers::merge(&game_dir, servers, pin, dry_run)?;
+
+    match (report.added.is_empty(), report.changed) {
+      (_, false) => println!("\"{}\" is up to date", mp),
+      (true, true) => println!("\"{}\" reordered", mp),
+      (false, true) => println!("\"{}\" added {}", mp, report.added.join(", ")),
+    }
+  }
+
+  Ok(())
+}
+
+fn logs(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let logs = CrashLogs::collect(settings, &mp)?;
+  let output = matches.value_of("output")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from(format!("{}-logs.zip", mp)));
+
+  logs.write_zip(&output)?;
+
+  print!("{}", logs);
+  println!("Saved logs to {}", output.display());
+
+  Ok(())
+}
+
+fn launch(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let mp = find_modpack(settings, matches)?;
+  let outcome = crate::launch::launch(settings, &mp)?;
+
+  println!("Launching \"{}\", {}", mp, outcome);
+
+  Ok(())
+}
+
+fn shortcuts(settings: &ApplicationSettings) -> Result<()> {
+  let written = crate::shortcuts::regenerate(settings)?;
+
+  println!("Wrote {} desktop entries to {}", written, crate::shortcuts::applications_dir().display());
+
+  Ok(())
+}
+
+fn watch(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let interval = matches.value_of("interval").unwrap_or_default().parse::<u64>()?;
+
+  println!("Watching linked {} instances, stop with Ctrl+C", settings.source);
+
+  crate::snapshot::watch(settings, Duration::from_secs(interval), matches.is_present("snapshot"), &mut |mp, snapshot| {
+    match snapshot {
+      None => println!("\"{}\" changed", mp),
+      Some(Ok(Some(snapshot))) => println!("\"{}\" changed, saved snapshot {}", mp, snapshot.id()),
+      Some(Ok(None)) => println!("\"{}\" changed, mods and configs are the same as in the last snapshot", mp),
+      Some(Err(err)) => eprintln!("\"{}\" changed, snapshot failed: {}", mp, err),
+    }
+  });
+
+  Ok(())
+}
+
+/// State of an instance, of a snapshot file, or of a snapshot given as `<instance>@<snapshot ID>`
+fn load_state(settings: &ApplicationSettings, query: &str) -> Result<InstanceState> {
+  let path = Path::new(query);
+
+  if path.is_file() {
+    return InstanceState::read(path);
+  }
+
+  if let Some((instance, id)) = query.rsplit_once('@') {
+    if let Some(mp) = ModPack::find(settings.cf_dir(), settings.source, instance) {
+      return Ok(crate::snapshot::find(&mp.instance()?.name, Some(id))?.state);
+    }
+  }
+
+  let mp = ModPack::find(settings.cf_dir(), settings.source, query)
+    .ok_or_else(|| format!("No {} instance or snapshot called \"{}\"", settings.source, query))?;
+
+  InstanceState::capture(&mp)
+}
+
+fn diff(settings: &ApplicationSettings, matches: &ArgMatches) -> Result<()> {
+  let a = load_state(settings, matches.value_of("a").unwrap_or_default())?;
+  let b = load_state(settings, matches.value_of("b").unwrap_or_default())?;
+  let diff = crate::diff::InstanceDiff::new(&a, &b);
+
+  match matches.is_present("json") {
+    true => println!("{}", serde_json::to_string_pretty(&diff)?),
+    false => print!("{}", diff),
+  }
+
+  Ok(())
+}
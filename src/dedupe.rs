@@ -0,0 +1,441 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{copy, create_dir_all, File, hard_link, Metadata, remove_file, rename};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::export::ADDON_DIRS;
+use crate::hash::sha1;
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+use crate::state::{data_dir, now};
+
+/// Identical files on the same filesystem
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+  pub sha1: String,
+  pub size: u64,
+  /// Kept as it is, the copies become hardlinks to it
+  pub original: PathBuf,
+  /// Files that aren't hardlinks to `original` yet
+  pub copies: Vec<PathBuf>,
+  /// Bytes freed by linking the copies
+  pub savings: u64,
+}
+
+/// A copy replaced by a hardlink, recorded so it can be undone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedFile {
+  pub path: PathBuf,
+  pub original: PathBuf,
+  pub sha1: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Journal {
+  pub created: u64,
+  pub files: Vec<LinkedFile>,
+}
+
+impl Journal {
+  fn read(path: &Path) -> Result<Journal> {
+    Ok(serde_json::from_reader(File::open(path)?)?)
+  }
+}
+
+/// Outcome of [`undo`]
+#[derive(Debug, Clone, Default)]
+pub struct UndoReport {
+  pub restored: usize,
+  /// Links that couldn't be turned back into files, they stay in their journal for the next try
+  pub failed: Vec<(PathBuf, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DedupeReport {
+  pub linked: usize,
+  pub saved: u64,
+  /// Copies that couldn't be linked, usually because they're on another filesystem
+  pub skipped: Vec<(PathBuf, String)>,
+  pub journal: Option<PathBuf>,
+}
+
+/// Device and inode, `None` where the platform doesn't expose them
+#[cfg(unix)]
+fn identity(meta: &Metadata) -> Option<(u64, u64)> {
+  use std::os::unix::fs::MetadataExt;
+
+  Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_meta: &Metadata) -> Option<(u64, u64)> {
+  None
+}
+
+/// Where [`dedupe`] keeps its journals by default
+pub fn journals_dir() -> PathBuf {
+  data_dir().join("dedupe")
+}
+
+/// Jars, resource packs and shader packs of every instance, symlinks aren't followed so shared folders
+/// aren't counted twice
+fn addon_files(settings: &ApplicationSettings) -> Vec<(PathBuf, Metadata)> {
+  let mut files = Vec::new();
+
+  for mp in ModPack::list(settings.cf_dir(), settings.source, &mut None) {
+    let game_dir = match mp.instance() {
+      Ok(instance) => instance.game_dir,
+      Err(_) => continue,
+    };
+
+    for dir in ADDON_DIRS {
+      for entry in WalkDir::new(game_dir.join(dir)).min_depth(1).sort_by_file_name().into_iter().flatten() {
+        match entry.metadata() {
+          Ok(meta) if meta.is_file() && meta.len() > 0 => files.push((entry.into_path(), meta)),
+          _ => {}
+        }
+      }
+    }
+  }
+
+  files
+}
+
+/// Finds identical addon files across all instances. Only files of the same size are hashed
+pub fn find_duplicates(settings: &ApplicationSettings) -> Result<Vec<Duplicates>> {
+  let mut by_size = HashMap::<u64, Vec<(PathBuf, Metadata)>>::new();
+
+  for (path, meta) in addon_files(settings) {
+    by_size.entry(meta.len()).or_default().push((path, meta));
+  }
+
+  let mut groups = BTreeMap::<(String, u64), Vec<(PathBuf, Option<(u64, u64)>)>>::new();
+
+  for files in by_size.into_values().filter(|it| it.len() > 1) {
+    for (path, meta) in files {
+      let id = identity(&meta);
+      let device = id.map(|it| it.0).unwrap_or(0);
+
+      groups.entry((sha1(&path)?, device)).or_default().push((path, id));
+    }
+  }
+
+  let mut duplicates = Vec::new();
+
+  for ((hash, _), mut files) in groups.into_iter().filter(|(_, files)| files.len() > 1) {
+    files.sort();
+
+    let (original, original_id) = files.remove(0);
+    let copies = files.iter()
+      .filter(|(_, id)| id.is_none() || *id != original_id)
+      .collect::<Vec<_>>();
+
+    if copies.is_empty() {
+      continue;
+    }
+
+    let size = original.metadata()?.len();
+    // Copies already linked to each other only free their space once
+    let distinct = copies.iter()
+      .map(|(path, id)| id.map(|it| it.1.to_string()).unwrap_or_else(|| path.display().to_string()))
+      .collect::<BTreeSet<_>>();
+
+    duplicates.push(Duplicates {
+      sha1: hash,
+      size,
+      savings: size * distinct.len() as u64,
+      copies: copies.into_iter().map(|(path, _)| path.clone()).collect(),
+      original,
+    });
+  }
+
+  duplicates.sort_by(|a, b| b.savings.cmp(&a.savings).then_with(|| a.original.cmp(&b.original)));
+
+  Ok(duplicates)
+}
+
+/// Replaces `path` with a hardlink to `original` without a moment where `path` doesn't exist
+fn replace_with_link(original: &Path, path: &Path) -> Result<()> {
+  let temp = path.with_file_name(format!("{}.dedupe", path.file_name().unwrap_or_default().to_string_lossy()));
+
+  hard_link(original, &temp)?;
+
+  if let Err(err) = rename(&temp, path) {
+    let _ = remove_file(&temp);
+    return Err(err.into());
+  }
+
+  Ok(())
+}
+
+/// Replaces every copy in `duplicates` with a hardlink to its original. What was linked is written to a
+/// journal in `journals`, so [`undo`] can turn the links back into separate files
+pub fn dedupe(duplicates: &[Duplicates], journals: &Path) -> Result<DedupeReport> {
+  let mut report = DedupeReport::default();
+  let mut journal = Journal { created: now(), files: Vec::new() };
+
+  for group in duplicates {
+    let mut linked = BTreeSet::new();
+
+    for path in &group.copies {
+      // Checked again, the file may have changed since it was hashed
+      let unchanged = sha1(path).map(|it| it == group.sha1).unwrap_or(false);
+
+      if !unchanged {
+        report.skipped.push((path.clone(), String::from("Changed since it was hashed")));
+        continue;
+      }
+
+      let id = path.metadata().ok().and_then(|it| identity(&it));
+
+      match replace_with_link(&group.original, path) {
+        Ok(_) => {
+          report.linked += 1;
+
+          if id.is_none() || linked.insert(id) {
+            report.saved += group.size;
+          }
+
+          journal.files.push(LinkedFile {
+            path: path.clone(),
+            original: group.original.clone(),
+            sha1: group.sha1.clone(),
+          });
+        }
+        Err(err) => report.skipped.push((path.clone(), err.to_string())),
+      }
+    }
+  }
+
+  if !journal.files.is_empty() {
+    let path = (0..)
+      .map(|it| match it {
+        0 => journals.join(format!("{}.json", journal.created)),
+        _ => journals.join(format!("{}-{}.json", journal.created, it)),
+      })
+      .find(|it| !it.exists())
+      .unwrap_or_default();
+
+    create_dir_all(journals)?;
+    serde_json::to_writer_pretty(File::create(&path)?, &journal)?;
+    report.journal = Some(path);
+  }
+
+  Ok(report)
+}
+
+/// Turns a hardlink back into a file of its own
+fn unlink_copy(file: &LinkedFile) -> Result<()> {
+  let temp = file.path.with_file_name(format!("{}.undo", file.path.file_name().unwrap_or_default().to_string_lossy()));
+  let result = copy(&file.path, &temp).and_then(|_| rename(&temp, &file.path));
+
+  if result.is_err() {
+    let _ = remove_file(&temp);
+  }
+
+  result?;
+
+  Ok(())
+}
+
+/// Turns every hardlink made by [`dedupe`] back into a file of its own, newest journal in `journals` first.
+/// Files that changed or were removed since are left alone. Failures don't stop the others, a journal is only
+/// removed once all of its files are done and keeps the failed ones otherwise
+pub fn undo(journals: &Path) -> Result<UndoReport> {
+  let mut paths = journals
+    .read_dir()
+    .map(|it| it.flatten()
+      .map(|it| it.path())
+      .filter(|it| it.extension().map(|it| it == "json").unwrap_or(false))
+      .collect::<Vec<_>>()
+    )
+    .unwrap_or_default();
+
+  paths.sort();
+
+  let mut report = UndoReport::default();
+
+  for path in paths.into_iter().rev() {
+    let mut journal = match Journal::read(&path) {
+      Ok(journal) => journal,
+      Err(err) => {
+        report.failed.push((path, err.to_string()));
+        continue;
+      }
+    };
+
+    journal.files.retain(|file| {
+      if sha1(&file.path).map(|it| it != file.sha1).unwrap_or(true) {
+        return false;
+      }
+
+      match unlink_copy(file) {
+        Ok(()) => {
+          report.restored += 1;
+          false
+        }
+        Err(err) => {
+          report.failed.push((file.path.clone(), err.to_string()));
+          true
+        }
+      }
+    });
+
+    match journal.files.is_empty() {
+      true => remove_file(&path)?,
+      false => serde_json::to_writer_pretty(File::create(&path)?, &journal)?,
+    }
+  }
+
+  Ok(report)
+}
+
+/// `bytes` in the largest unit that keeps it above 1
+pub fn format_size(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+  let mut size = bytes as f64;
+  let mut unit = "B";
+
+  for next in UNITS {
+    if size < 1024.0 {
+      break;
+    }
+
+    size /= 1024.0;
+    unit = next;
+  }
+
+  match unit {
+    "B" => format!("{} B", bytes),
+    _ => format!("{:.1} {}", size, unit),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::source::Source;
+
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dedupe-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// Modrinth App profiles in `dir/profiles` with the given `mods/` files
+  fn settings(dir: &Path, instances: &[(&str, &[(&str, &str)])]) -> ApplicationSettings {
+    for (name, mods) in instances {
+      let profile = dir.join("profiles").join(name);
+
+      create_dir_all(profile.join("mods")).unwrap();
+      std::fs::write(profile.join("profile.json"), serde_json::json!({
+        "metadata": { "name": name, "game_version": "1.20.1", "loader": "fabric" }
+      }).to_string()).unwrap();
+
+      for (file, contents) in *mods {
+        std::fs::write(profile.join("mods").join(file), contents).unwrap();
+      }
+    }
+
+    ApplicationSettings {
+      cf_directory: Some(dir.join("profiles").to_string_lossy().to_string()),
+      source: Source::Modrinth,
+      ..Default::default()
+    }
+  }
+
+  fn mods(dir: &Path, instance: &str, file: &str) -> PathBuf {
+    dir.join("profiles").join(instance).join("mods").join(file)
+  }
+
+  #[test]
+  fn duplicates_are_grouped_by_contents() {
+    let dir = temp_dir("find");
+    let settings = settings(&dir, &[
+      ("A", &[("x.jar", "same"), ("z.jar", "diff")]),
+      ("B", &[("x.jar", "same")]),
+      ("C", &[("y.jar", "same"), ("other.jar", "unique contents")]),
+    ]);
+
+    let duplicates = find_duplicates(&settings).unwrap();
+
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].original, mods(&dir, "A", "x.jar"));
+    assert_eq!(duplicates[0].copies, [mods(&dir, "B", "x.jar"), mods(&dir, "C", "y.jar")]);
+    assert_eq!((duplicates[0].size, duplicates[0].savings), (4, 8));
+
+    // Copies that are hardlinks to the original already are done
+    std::fs::remove_file(mods(&dir, "B", "x.jar")).unwrap();
+    hard_link(mods(&dir, "A", "x.jar"), mods(&dir, "B", "x.jar")).unwrap();
+
+    let duplicates = find_duplicates(&settings).unwrap();
+
+    assert_eq!(duplicates[0].copies, [mods(&dir, "C", "y.jar")]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn dedupe_links_copies_and_undo_separates_them() {
+    let dir = temp_dir("roundtrip");
+    let journals = dir.join("journals");
+    let settings = settings(&dir, &[("A", &[("x.jar", "same")]), ("B", &[("x.jar", "same")])]);
+    let id = |path: PathBuf| identity(&path.metadata().unwrap()).unwrap();
+
+    let report = dedupe(&find_duplicates(&settings).unwrap(), &journals).unwrap();
+
+    assert_eq!((report.linked, report.saved), (1, 4));
+    assert!(report.skipped.is_empty());
+    assert!(report.journal.as_ref().unwrap().is_file());
+    assert_eq!(id(mods(&dir, "A", "x.jar")), id(mods(&dir, "B", "x.jar")));
+    assert!(find_duplicates(&settings).unwrap().is_empty());
+
+    let undone = undo(&journals).unwrap();
+
+    assert_eq!((undone.restored, undone.failed.len()), (1, 0));
+    assert_ne!(id(mods(&dir, "A", "x.jar")), id(mods(&dir, "B", "x.jar")));
+    assert_eq!(std::fs::read_to_string(mods(&dir, "B", "x.jar")).unwrap(), "same");
+    assert_eq!(journals.read_dir().unwrap().count(), 0);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn failed_undos_stay_in_the_journal() {
+    let dir = temp_dir("undo");
+    let journals = dir.join("journals");
+    let settings = settings(&dir, &[
+      ("A", &[("x.jar", "same"), ("y.jar", "also")]),
+      ("B", &[("x.jar", "same"), ("y.jar", "also")]),
+    ]);
+
+    let report = dedupe(&find_duplicates(&settings).unwrap(), &journals).unwrap();
+    let journal = report.journal.unwrap();
+    let blocker = mods(&dir, "B", "x.jar.undo");
+
+    // A directory where the copy has to go makes undoing that file fail
+    create_dir_all(blocker.join("inside")).unwrap();
+
+    let undone = undo(&journals).unwrap();
+
+    assert_eq!(undone.restored, 1);
+    assert_eq!(undone.failed.iter().map(|it| &it.0).collect::<Vec<_>>(), [&mods(&dir, "B", "x.jar")]);
+    assert_eq!(Journal::read(&journal).unwrap().files.len(), 1);
+
+    std::fs::remove_dir_all(blocker).unwrap();
+
+    let undone = undo(&journals).unwrap();
+
+    assert_eq!((undone.restored, undone.failed.len()), (1, 0));
+    assert!(!journal.exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
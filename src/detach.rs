@@ -0,0 +1,233 @@
+use std::fs::{copy, create_dir_all, File, remove_dir_all, rename};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::directories::MultiMCDirectory;
+use crate::launcher::game_dir;
+use crate::link::Result;
+use crate::marker::{is_marked, is_symlink_to, Marker, MarkerKind};
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+  Copying,
+  Verifying,
+}
+
+/// Bytes processed so far in the current stage
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+  pub stage: Stage,
+  pub done: u64,
+  pub total: u64,
+}
+
+/// Files and symlinks below `dir`, relative to it, along with the total size of the files
+fn entries(dir: &Path) -> Result<(Vec<PathBuf>, u64)> {
+  let mut entries = Vec::new();
+  let mut total = 0;
+
+  for entry in WalkDir::new(dir).min_depth(1) {
+    let entry = entry?;
+
+    if entry.file_type().is_dir() {
+      continue;
+    }
+
+    if entry.file_type().is_file() {
+      total += entry.metadata()?.len();
+    }
+
+    entries.push(entry.path().strip_prefix(dir)?.to_path_buf());
+  }
+
+  Ok((entries, total))
+}
+
+pub fn same_contents(a: &Path, b: &Path) -> std::io::Result<bool> {
+  if a.metadata()?.len() != b.metadata()?.len() {
+    return Ok(false);
+  }
+
+  let mut a = BufReader::new(File::open(a)?);
+  let mut b = BufReader::new(File::open(b)?);
+  let mut a_buf = [0; 64 * 1024];
+  let mut b_buf = [0; 64 * 1024];
+
+  loop {
+    let read = a.read(&mut a_buf)?;
+
+    if read == 0 {
+      return Ok(true);
+    }
+
+    b.read_exact(&mut b_buf[..read])?;
+
+    if a_buf[..read] != b_buf[..read] {
+      return Ok(false);
+    }
+  }
+}
+
+/// Copies `from` into `to` (which must not exist), nested symlinks are recreated rather than followed.
+/// Every file is compared with its original afterwards, returns the number of entries copied
+pub fn copy_dir(from: &Path, to: &Path, progress: &mut dyn FnMut(Progress)) -> Result<usize> {
+  let (entries, total) = entries(from)?;
+  let mut done = 0;
+
+  create_dir_all(to)?;
+  progress(Progress { stage: Stage::Copying, done, total });
+
+  for entry in &entries {
+    let source = from.join(entry);
+    let dest = to.join(entry);
+
+    if let Some(parent) = dest.parent() {
+      create_dir_all(parent)?;
+    }
+
+    if source.symlink_metadata()?.file_type().is_symlink() {
+      let target = source.read_link()?;
+
+      match source.is_dir() {
+        true => symlink::symlink_dir(target, &dest)?,
+        false => symlink::symlink_file(target, &dest)?,
+      }
+    } else {
+      done += copy(&source, &dest)?;
+      progress(Progress { stage: Stage::Copying, done, total });
+    }
+  }
+
+  done = 0;
+  progress(Progress { stage: Stage::Verifying, done, total });
+
+  for entry in &entries {
+    let source = from.join(entry);
+
+    if source.symlink_metadata()?.file_type().is_symlink() {
+      continue;
+    }
+
+    if !same_contents(&source, &to.join(entry))? {
+      return Err(format!("Copy of {} doesn't match the original", entry.display()).into());
+    }
+
+    done += source.metadata()?.len();
+    progress(Progress { stage: Stage::Verifying, done, total });
+  }
+
+  Ok(entries.len())
+}
+
+/// Replaces the game directory symlink made by [`crate::link::link`] with a full, verified copy of the
+/// source instance, so updates in the source launcher no longer affect it
+pub fn detach(
+  settings: &ApplicationSettings,
+  mmc: &MultiMCDirectory,
+  mp: &ModPack,
+  progress: &mut dyn FnMut(Progress),
+) -> Result<usize> {
+  let instance = mp.instance()?;
+  let mmc_path = mp.mmc_path(mmc).ok_or("This instance isn't in the launcher")?;
+  let link = game_dir(&mmc_path);
+
+  if !is_marked(&mmc_path, MarkerKind::Link) && !is_symlink_to(&link, &instance.game_dir) {
+    return Err(Box::from("This instance wasn't linked by this application"));
+  }
+
+  crate::backup::backup_saves(settings, &instance.name, &instance.game_dir, "detach")?;
+
+  let copy_path = mmc_path.join(format!("{}.detaching", settings.target.launcher().game_dir_name()));
+
+  if copy_path.exists() {
+    remove_dir_all(&copy_path)?;
+  }
+
+  let copied = match copy_dir(&instance.game_dir, &copy_path, progress) {
+    Ok(copied) => copied,
+    Err(err) => {
+      let _ = remove_dir_all(&copy_path);
+      return Err(err);
+    }
+  };
+
+  symlink::remove_symlink_dir(&link)?;
+
+  // Without the copy in place the instance has no game directory at all, so put the link back
+  if let Err(err) = rename(&copy_path, &link) {
+    let _ = remove_dir_all(&copy_path);
+
+    return match symlink::symlink_dir(&instance.game_dir, &link) {
+      Ok(()) => Err(err.into()),
+      Err(restore) => Err(format!("{}, and the link couldn't be restored: {}", err, restore).into()),
+    };
+  }
+
+  Marker::new(MarkerKind::Detached, &instance.game_dir).write(&mmc_path)?;
+  crate::link::write_managed_pack_keys(settings.target.launcher(), &mmc_path, &instance)?;
+
+  Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("detach-{}-{}", std::process::id(), name));
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn same_contents_compares_every_byte() {
+    let dir = temp_dir("same");
+    let big = vec![7; 200 * 1024];
+    let mut changed = big.clone();
+
+    *changed.last_mut().unwrap() = 8;
+
+    std::fs::write(dir.join("a"), &big).unwrap();
+    std::fs::write(dir.join("b"), &big).unwrap();
+    std::fs::write(dir.join("c"), &changed).unwrap();
+    std::fs::write(dir.join("d"), &big[1..]).unwrap();
+
+    assert!(same_contents(&dir.join("a"), &dir.join("b")).unwrap());
+    assert!(!same_contents(&dir.join("a"), &dir.join("c")).unwrap());
+    assert!(!same_contents(&dir.join("a"), &dir.join("d")).unwrap());
+    assert!(same_contents(&dir.join("a"), &dir.join("missing")).is_err());
+
+    remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn copy_dir_copies_files_and_recreates_symlinks() {
+    let dir = temp_dir("copy");
+    let from = dir.join("from");
+
+    create_dir_all(from.join("mods")).unwrap();
+    create_dir_all(dir.join("shared")).unwrap();
+    std::fs::write(from.join("options.txt"), "fov:70").unwrap();
+    std::fs::write(from.join("mods/a.jar"), [1, 2, 3]).unwrap();
+    std::fs::write(dir.join("shared/b.png"), [4, 5]).unwrap();
+    symlink::symlink_dir(dir.join("shared"), from.join("screenshots")).unwrap();
+
+    let mut stages = Vec::new();
+    let copied = copy_dir(&from, &dir.join("to"), &mut |it| stages.push((it.stage, it.done, it.total))).unwrap();
+    let to = dir.join("to");
+
+    assert_eq!(copied, 3);
+    assert_eq!(std::fs::read_to_string(to.join("options.txt")).unwrap(), "fov:70");
+    assert_eq!(std::fs::read(to.join("mods/a.jar")).unwrap(), [1, 2, 3]);
+    assert!(to.join("screenshots").symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(to.join("screenshots").read_link().unwrap(), dir.join("shared"));
+    assert_eq!(stages.last(), Some(&(Stage::Verifying, 9, 9)));
+
+    remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,405 @@
+use std::fmt::Formatter;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::cfg::read_cfg;
+use crate::directories::home;
+use crate::launcher::{Target, TargetLauncher};
+use crate::source::Source;
+use crate::link::CFMinecraftInstance;
+
+/// A directory that looks like an instance root, along with why it was picked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+  pub path: PathBuf,
+  pub reason: String,
+  /// Higher is more likely to be what the user wants
+  pub score: u8,
+}
+
+impl std::fmt::Display for Candidate {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} ({})", self.path.display(), self.reason)
+  }
+}
+
+const SCORE_PORTABLE: u8 = 40;
+const SCORE_CONFIG: u8 = 30;
+const SCORE_INSTANCE: u8 = 20;
+const SCORE_WINE: u8 = 15;
+const SCORE_DEFAULT: u8 = 10;
+
+/// Directories next to (and one level above) the running executable,
+/// this is where portable installs usually end up
+fn exe_dirs() -> Vec<PathBuf> {
+  let exe_dir = std::env::current_exe()
+    .ok()
+    .and_then(|it| it.parent().map(Path::to_path_buf));
+
+  let mut dirs = Vec::new();
+
+  if let Some(exe_dir) = exe_dir {
+    if let Some(parent) = exe_dir.parent() {
+      if let Ok(entries) = parent.read_dir() {
+        dirs.extend(entries.flatten().map(|it| it.path()).filter(|it| it.is_dir()));
+      }
+    }
+
+    dirs.insert(0, exe_dir);
+  }
+
+  dirs
+}
+
+/// Sorts by score, highest first, and merges candidates pointing to the same directory
+fn rank(candidates: Vec<Candidate>) -> Vec<Candidate> {
+  let mut ranked = Vec::<Candidate>::new();
+
+  for candidate in candidates {
+    if !candidate.path.is_dir() {
+      continue;
+    }
+
+    let key = candidate.path.canonicalize().unwrap_or_else(|_| candidate.path.clone());
+
+    match ranked.iter_mut().find(|it| it.path.canonicalize().unwrap_or_else(|_| it.path.clone()) == key) {
+      Some(existing) if existing.score >= candidate.score => {}
+      Some(existing) => *existing = candidate,
+      None => ranked.push(candidate),
+    }
+  }
+
+  ranked.sort_by_key(|it| std::cmp::Reverse(it.score));
+  ranked
+}
+
+/// Reads `InstanceDir` from a launcher config, which may be relative to the config's directory
+fn instance_dir_from_cfg(cfg_path: &Path) -> Option<PathBuf> {
+  let cfg = read_cfg(cfg_path).ok()?;
+  let instance_dir = cfg.get("InstanceDir").map(String::as_str).unwrap_or("instances");
+  let instance_dir = Path::new(instance_dir);
+
+  if instance_dir.is_absolute() {
+    Some(instance_dir.to_path_buf())
+  } else {
+    Some(cfg_path.parent()?.join(instance_dir))
+  }
+}
+
+/// The directory holding the launcher config whose `InstanceDir` is `instances_dir`, out of `dirs`
+fn data_dir_in(config_file: &str, instances_dir: &Path, dirs: Vec<PathBuf>) -> Option<PathBuf> {
+  let key = |it: &Path| it.canonicalize().unwrap_or_else(|_| it.to_path_buf());
+  let wanted = key(instances_dir);
+
+  dirs.into_iter()
+    .find(|dir| instance_dir_from_cfg(&dir.join(config_file)).map(|it| key(&it) == wanted).unwrap_or(false))
+}
+
+/// Data directory of `launcher` for the instances in `instances_dir`. Instances usually sit directly in it,
+/// otherwise the portable installs and data directories detection looks at are searched for a config with that
+/// `InstanceDir`, falling back to the directory above `instances_dir`
+pub fn launcher_data_dir<L: TargetLauncher + ?Sized>(launcher: &L, instances_dir: &Path) -> PathBuf {
+  let parent = instances_dir.parent().map(Path::to_path_buf).unwrap_or_default();
+  let dirs = std::iter::once(parent.clone())
+    .chain(exe_dirs())
+    .chain(launcher.data_dirs())
+    .collect();
+
+  data_dir_in(launcher.config_file(), instances_dir, dirs).unwrap_or(parent)
+}
+
+/// Finds instance directories of the `target` launcher from its config files and portable layouts
+pub fn target_candidates(target: Target) -> Vec<Candidate> {
+  let launcher = target.launcher();
+  let mut candidates = Vec::new();
+
+  for dir in exe_dirs() {
+    if !launcher.executables().iter().any(|it| dir.join(it).exists()) {
+      continue;
+    }
+
+    let cfg_path = dir.join(launcher.config_file());
+    let path = instance_dir_from_cfg(&cfg_path).unwrap_or_else(|| dir.join("instances"));
+
+    candidates.push(Candidate {
+      reason: format!("portable install next to this application in {}", dir.display()),
+      score: SCORE_PORTABLE,
+      path,
+    });
+  }
+
+  for dir in launcher.data_dirs() {
+    let cfg_path = dir.join(launcher.config_file());
+
+    if let Some(path) = instance_dir_from_cfg(&cfg_path) {
+      candidates.push(Candidate {
+        reason: format!("InstanceDir from {}", cfg_path.display()),
+        score: SCORE_CONFIG,
+        path,
+      });
+    }
+
+    candidates.push(Candidate {
+      path: dir.join("instances"),
+      reason: format!("default {} location", launcher.name()),
+      score: SCORE_DEFAULT,
+    });
+  }
+
+  if target == Target::MultiMC {
+    candidates.push(Candidate {
+      path: crate::directories::MultiMCDirectory::fallback(),
+      reason: String::from("default MultiMC location"),
+      score: SCORE_DEFAULT,
+    });
+  }
+
+  rank(candidates)
+}
+
+fn curseforge_default_dirs() -> Vec<PathBuf> {
+  let home = home();
+
+  vec![
+    home.join("curseforge").join("minecraft").join("Instances"),
+    home.join("Documents").join("curseforge").join("minecraft").join("Instances"),
+    home.join("Twitch").join("Minecraft").join("Instances"),
+  ]
+}
+
+/// Directories CurseForge keeps its settings in, natively and inside Wine prefixes
+fn curseforge_settings_dirs() -> Vec<PathBuf> {
+  #[allow(unused_mut)]
+  let mut dirs = crate::directories::app_data_dirs("CurseForge", None);
+
+  #[cfg(not(windows))]
+  for prefix in crate::wine::prefixes() {
+    dirs.extend(crate::wine::curseforge_settings_dirs(&prefix.path));
+  }
+
+  dirs
+}
+
+fn json_strings<'a>(value: &'a serde_json::Value, strings: &mut Vec<&'a str>) {
+  match value {
+    serde_json::Value::String(it) => strings.push(it),
+    serde_json::Value::Array(values) => values.iter().for_each(|it| json_strings(it, strings)),
+    serde_json::Value::Object(values) => values.values().for_each(|it| json_strings(it, strings)),
+    _ => {}
+  }
+}
+
+/// Instance directories below the folders named in CurseForge's settings files in `dir`. The key holding the
+/// modding folder has changed between CurseForge versions, so every absolute path with instances below it counts
+fn instance_dirs_from_settings(dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+  let mut found = Vec::new();
+  let files = dir.read_dir()
+    .map(|it| it.flatten().map(|it| it.path()).collect::<Vec<_>>())
+    .unwrap_or_default();
+
+  for file in files.into_iter().filter(|it| it.extension().map(|it| it == "json").unwrap_or(false)) {
+    let value = match File::open(&file).ok().and_then(|it| serde_json::from_reader::<_, serde_json::Value>(it).ok()) {
+      Some(value) => value,
+      None => continue,
+    };
+
+    let mut strings = Vec::new();
+    json_strings(&value, &mut strings);
+
+    for string in strings {
+      if !crate::wine::is_windows_path(string) && !Path::new(string).is_absolute() {
+        continue;
+      }
+
+      let path = match crate::wine::host_path(string, dir) {
+        Some(path) => path,
+        None => continue,
+      };
+
+      let instances = [path.join("minecraft").join("Instances"), path.join("Instances"), path]
+        .into_iter()
+        .find(|it| it.file_name().map(|it| it == "Instances").unwrap_or(false) && it.is_dir());
+
+      if let Some(instances) = instances.filter(|it| !found.iter().any(|(path, _)| path == it)) {
+        found.push((instances, file.clone()));
+      }
+    }
+  }
+
+  found
+}
+
+/// `installPath` recorded in the first `minecraftinstance.json` found in `dir`
+fn install_root_from_instances(dir: &Path) -> Option<PathBuf> {
+  dir.read_dir().ok()?
+    .flatten()
+    .map(|it| it.path().join("minecraftinstance.json"))
+    .filter_map(|it| File::open(it).ok())
+    .filter_map(|it| serde_json::from_reader::<_, CFMinecraftInstance>(it).ok())
+    .filter_map(|it| it.host_install_path(dir))
+    .find_map(|it| it.parent().map(Path::to_path_buf))
+}
+
+/// Finds CurseForge instance directories from CurseForge's settings, the default locations, Wine prefixes
+/// and the `installPath` recorded by CurseForge in each instance
+pub fn curseforge_candidates() -> Vec<Candidate> {
+  let mut candidates = curseforge_default_dirs()
+    .into_iter()
+    .map(|path| Candidate {
+      path,
+      reason: String::from("default CurseForge location"),
+      score: SCORE_DEFAULT,
+    })
+    .collect::<Vec<_>>();
+
+  for dir in curseforge_settings_dirs() {
+    for (path, file) in instance_dirs_from_settings(&dir) {
+      candidates.push(Candidate {
+        reason: format!("modding folder from {}", file.display()),
+        score: SCORE_CONFIG,
+        path,
+      });
+    }
+  }
+
+  #[cfg(not(windows))]
+  for prefix in crate::wine::prefixes() {
+    for path in crate::wine::curseforge_instance_dirs(&prefix.path) {
+      candidates.push(Candidate {
+        path,
+        reason: format!("{} prefix {}", prefix.kind, prefix.path.display()),
+        score: SCORE_WINE,
+      });
+    }
+  }
+
+  let recorded = candidates.iter()
+    .filter_map(|it| install_root_from_instances(&it.path))
+    .map(|path| Candidate {
+      path,
+      reason: String::from("installPath from minecraftinstance.json"),
+      score: SCORE_INSTANCE,
+    })
+    .collect::<Vec<_>>();
+
+  candidates.extend(recorded);
+  rank(candidates)
+}
+
+/// Finds instance directories of the `source` launcher
+pub fn source_candidates(source: Source) -> Vec<Candidate> {
+  if source == Source::CurseForge {
+    return curseforge_candidates();
+  }
+
+  let launcher = source.launcher();
+  let candidates = launcher.default_dirs()
+    .into_iter()
+    .map(|path| Candidate {
+      reason: format!("default {} location", launcher.name()),
+      score: if launcher.discover(&path).is_empty() { SCORE_DEFAULT } else { SCORE_INSTANCE },
+      path,
+    })
+    .collect();
+
+  rank(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::{create_dir_all, write};
+
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("detect-{}-{}", std::process::id(), name));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn candidate(path: &Path, score: u8) -> Candidate {
+    Candidate { path: path.to_path_buf(), reason: score.to_string(), score }
+  }
+
+  #[test]
+  fn instance_dir_is_relative_to_the_config() {
+    let dir = temp_dir("cfg");
+
+    write(dir.join("multimc.cfg"), "InstanceDir=games/instances\n").unwrap();
+    assert_eq!(instance_dir_from_cfg(&dir.join("multimc.cfg")), Some(dir.join("games/instances")));
+
+    write(dir.join("multimc.cfg"), "InstanceDir=/srv/instances\n").unwrap();
+    assert_eq!(instance_dir_from_cfg(&dir.join("multimc.cfg")), Some(PathBuf::from("/srv/instances")));
+
+    write(dir.join("multimc.cfg"), "Language=en\n").unwrap();
+    assert_eq!(instance_dir_from_cfg(&dir.join("multimc.cfg")), Some(dir.join("instances")));
+
+    assert_eq!(instance_dir_from_cfg(&dir.join("missing.cfg")), None);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn data_dir_is_found_by_instance_dir() {
+    let dir = temp_dir("data-dir");
+    let (data, instances) = (dir.join("data"), dir.join("elsewhere/instances"));
+
+    create_dir_all(&data).unwrap();
+    create_dir_all(&instances).unwrap();
+    write(data.join("prismlauncher.cfg"), format!("InstanceDir={}\n", instances.display())).unwrap();
+
+    let dirs = vec![dir.join("elsewhere"), dir.join("missing"), data.clone()];
+
+    assert_eq!(data_dir_in("prismlauncher.cfg", &instances, dirs.clone()), Some(data.clone()));
+    assert_eq!(data_dir_in("prismlauncher.cfg", &dir.join("other"), dirs), None);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn ranking_keeps_the_best_reason_per_directory() {
+    let dir = temp_dir("rank");
+    let (a, b) = (dir.join("a"), dir.join("b"));
+
+    create_dir_all(&a).unwrap();
+    create_dir_all(&b).unwrap();
+    symlink::symlink_dir(&a, dir.join("link")).unwrap();
+
+    let ranked = rank(vec![
+      candidate(&a, SCORE_DEFAULT),
+      candidate(&dir.join("missing"), SCORE_PORTABLE),
+      candidate(&b, SCORE_INSTANCE),
+      candidate(&dir.join("link"), SCORE_CONFIG),
+      candidate(&b, SCORE_DEFAULT),
+    ]);
+
+    assert_eq!(ranked.iter().map(|it| it.score).collect::<Vec<_>>(), [SCORE_CONFIG, SCORE_INSTANCE]);
+    assert_eq!(ranked[0].path, dir.join("link"));
+    assert_eq!(ranked[1].path, b);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn modding_folder_comes_from_any_path_in_the_settings() {
+    let dir = temp_dir("settings");
+    let instances = dir.join("Games/curseforge/minecraft/Instances");
+
+    create_dir_all(&instances).unwrap();
+    write(
+      dir.join("storage.json"),
+      serde_json::json!({
+        "url": "https://www.curseforge.com",
+        "relative": "minecraft",
+        "games": [{ "minecraft": { "folder": dir.join("Games/curseforge").display().to_string() } }]
+      }).to_string(),
+    ).unwrap();
+    write(dir.join("broken.json"), "{").unwrap();
+
+    assert_eq!(instance_dirs_from_settings(&dir), [(instances, dir.join("storage.json"))]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,197 @@
+use std::fmt::Formatter;
+
+use serde::Serialize;
+
+use crate::state::{InstanceState, ModState};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+  pub before: String,
+  pub after: String,
+}
+
+impl Change {
+  fn of(before: &str, after: &str) -> Option<Change> {
+    (before != after).then(|| Change { before: before.to_string(), after: after.to_string() })
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModUpdate {
+  pub name: String,
+  pub version: Option<Change>,
+  pub file_name: Option<Change>,
+}
+
+/// Everything that differs between two states of an instance
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceDiff {
+  pub before: String,
+  pub after: String,
+  pub mc_version: Option<Change>,
+  pub loader: Option<Change>,
+  pub added: Vec<ModState>,
+  pub removed: Vec<ModState>,
+  pub updated: Vec<ModUpdate>,
+  pub configs_added: Vec<String>,
+  pub configs_removed: Vec<String>,
+  pub configs_changed: Vec<String>,
+}
+
+impl InstanceDiff {
+  pub fn new(before: &InstanceState, after: &InstanceState) -> Self {
+    let find = |state: &InstanceState, key: &str| state.mods.iter().find(|it| it.key == key).cloned();
+
+    let added = after.mods.iter().filter(|it| find(before, &it.key).is_none()).cloned().collect();
+    let removed = before.mods.iter().filter(|it| find(after, &it.key).is_none()).cloned().collect();
+    let updated = before.mods.iter()
+      .filter_map(|old| {
+        let new = find(after, &old.key)?;
+        let version = Change::of(&old.version, &new.version);
+        let file_name = Change::of(&old.file_name, &new.file_name);
+
+        (version.is_some() || file_name.is_some() || old.file_id != new.file_id)
+          .then(|| ModUpdate { name: new.name.clone(), version, file_name })
+      })
+      .collect();
+
+    Self {
+      before: before.name.clone(),
+      after: after.name.clone(),
+      mc_version: Change::of(&before.mc_version, &after.mc_version),
+      loader: Change::of(&before.loader, &after.loader),
+      added,
+      removed,
+      updated,
+      configs_added: after.configs.keys().filter(|it| !before.configs.contains_key(*it)).cloned().collect(),
+      configs_removed: before.configs.keys().filter(|it| !after.configs.contains_key(*it)).cloned().collect(),
+      configs_changed: before.configs.iter()
+        .filter(|(path, hash)| after.configs.get(*path).map(|it| it != *hash).unwrap_or(false))
+        .map(|(path, _)| path.clone())
+        .collect(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.mc_version.is_none()
+      && self.loader.is_none()
+      && self.added.is_empty()
+      && self.removed.is_empty()
+      && self.updated.is_empty()
+      && self.configs_added.is_empty()
+      && self.configs_removed.is_empty()
+      && self.configs_changed.is_empty()
+  }
+}
+
+impl std::fmt::Display for InstanceDiff {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.is_empty() {
+      return writeln!(f, "No differences");
+    }
+
+    if let Some(change) = &self.mc_version {
+      writeln!(f, "Minecraft: {} -> {}", change.before, change.after)?;
+    }
+
+    if let Some(change) = &self.loader {
+      writeln!(f, "Loader: {} -> {}", change.before, change.after)?;
+    }
+
+    for it in &self.added {
+      writeln!(f, "+ {} {} ({})", it.name, it.version, it.file_name)?;
+    }
+
+    for it in &self.removed {
+      writeln!(f, "- {} {} ({})", it.name, it.version, it.file_name)?;
+    }
+
+    for it in &self.updated {
+      match (&it.version, &it.file_name) {
+        (Some(version), _) => writeln!(f, "~ {} {} -> {}", it.name, version.before, version.after)?,
+        (None, Some(file_name)) => writeln!(f, "~ {} {} -> {}", it.name, file_name.before, file_name.after)?,
+        (None, None) => writeln!(f, "~ {} (different file)", it.name)?,
+      }
+    }
+
+    for (sign, paths) in [("+", &self.configs_added), ("-", &self.configs_removed), ("~", &self.configs_changed)] {
+      for path in paths {
+        writeln!(f, "{} {}", sign, path)?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::*;
+
+  fn state(name: &str, mc_version: &str, mods: &[(&str, &str, &str)], configs: &[(&str, &str)]) -> InstanceState {
+    InstanceState {
+      name: name.to_string(),
+      mc_version: mc_version.to_string(),
+      loader: String::from("forge-47.1.0"),
+      created: 0,
+      mods: mods.iter()
+        .map(|(key, version, file_name)| ModState {
+          key: key.to_string(),
+          name: key.trim_start_matches("mod:").to_string(),
+          version: version.to_string(),
+          file_name: file_name.to_string(),
+          project_id: None,
+          file_id: None,
+        })
+        .collect(),
+      configs: configs.iter().map(|(path, hash)| (path.to_string(), hash.to_string())).collect(),
+      files: BTreeMap::new(),
+    }
+  }
+
+  #[test]
+  fn differences_between_states() {
+    let before = state("Before", "1.20.1", &[
+      ("mod:create", "0.5.0", "create-0.5.0.jar"),
+      ("mod:jei", "15.0", "jei.jar"),
+      ("mod:same", "1", "same.jar"),
+    ], &[("config/a.toml", "1"), ("config/b.toml", "1"), ("config/c.toml", "1")]);
+    let after = state("After", "1.20.2", &[
+      ("mod:create", "0.5.1", "create-0.5.1.jar"),
+      ("mod:same", "1", "same.jar"),
+      ("mod:emi", "1.0", "emi.jar"),
+    ], &[("config/a.toml", "1"), ("config/b.toml", "2"), ("config/d.toml", "1")]);
+
+    let diff = InstanceDiff::new(&before, &after);
+
+    assert_eq!(diff.mc_version.as_ref().map(|it| (it.before.as_str(), it.after.as_str())), Some(("1.20.1", "1.20.2")));
+    assert!(diff.loader.is_none());
+    assert_eq!(diff.added.iter().map(|it| it.key.as_str()).collect::<Vec<_>>(), ["mod:emi"]);
+    assert_eq!(diff.removed.iter().map(|it| it.key.as_str()).collect::<Vec<_>>(), ["mod:jei"]);
+    assert_eq!(diff.updated.len(), 1);
+    assert_eq!(diff.configs_added, ["config/d.toml"]);
+    assert_eq!(diff.configs_removed, ["config/c.toml"]);
+    assert_eq!(diff.configs_changed, ["config/b.toml"]);
+
+    assert_eq!(diff.to_string(), concat!(
+      "Minecraft: 1.20.1 -> 1.20.2\n",
+      "+ emi 1.0 (emi.jar)\n",
+      "- jei 15.0 (jei.jar)\n",
+      "~ create 0.5.0 -> 0.5.1\n",
+      "+ config/d.toml\n",
+      "- config/c.toml\n",
+      "~ config/b.toml\n",
+    ));
+  }
+
+  #[test]
+  fn identical_states_have_no_differences() {
+    let state = state("Pack", "1.20.1", &[("mod:create", "0.5.1", "create.jar")], &[("config/a.toml", "1")]);
+    let diff = InstanceDiff::new(&state, &state);
+
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "No differences\n");
+  }
+}
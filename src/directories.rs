@@ -1,9 +1,14 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::process::ExitStatus;
 
+use crate::launcher::Target;
+use crate::link::Result;
+use crate::source::Source;
+
 macro_rules! directory {
   ($($name:ident),+) => {
-    $(#[derive(Debug, Clone)]
+    $(/// Empty by default, set it from the settings or `detect`
+    #[derive(Debug, Default, Clone)]
     pub struct $name {
       pub path: PathBuf,
     }
@@ -23,6 +28,56 @@ macro_rules! directory {
   };
 }
 
+/// Home directory of the current user, `USERPROFILE` on Windows
+pub fn home() -> PathBuf {
+  #[cfg(windows)] let home = std::env::var("USERPROFILE").unwrap_or_default();
+  #[cfg(not(windows))] let home = std::env::var("HOME").unwrap_or_default();
+
+  PathBuf::from(home)
+}
+
+/// Data directories of an application called `name`, `%APPDATA%`, `~/Library/Application Support` or
+/// `~/.local/share` (also lowercased on Linux), followed by the same directory inside the Flatpak `flatpak_id`
+#[allow(unused_variables)]
+pub fn app_data_dirs(name: &str, flatpak_id: Option<&str>) -> Vec<PathBuf> {
+  let home = home();
+  let mut dirs = Vec::new();
+
+  #[cfg(windows)] dirs.push(PathBuf::from(std::env::var("APPDATA").unwrap_or_default()).join(name));
+  #[cfg(target_os = "macos")] dirs.push(home.join("Library/Application Support").join(name));
+  #[cfg(target_os = "linux")] {
+    dirs.push(home.join(".local/share").join(name));
+
+    if name.to_lowercase() != name {
+      dirs.push(home.join(".local/share").join(name.to_lowercase()));
+    }
+
+    if let Some(id) = flatpak_id {
+      dirs.push(flatpak_data_dir(id).join(name));
+    }
+  }
+
+  dirs
+}
+
+/// What the Flatpak app `id` sees as `~/.local/share`
+pub fn flatpak_data_dir(id: &str) -> PathBuf {
+  home().join(".var/app").join(id).join("data")
+}
+
+/// Rejects instance names that aren't a single directory name, names come from zips, instance files and the
+/// command line and must not place anything outside the directory they're joined onto
+pub fn check_name(name: &str) -> Result<()> {
+  let mut components = Path::new(name).components();
+  let single = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+
+  if !single || name.contains(['/', '\\']) || name.trim().is_empty() {
+    return Err(format!("\"{}\" can't be used as an instance name", name).into());
+  }
+
+  Ok(())
+}
+
 pub trait Directory {
   fn new<P: AsRef<Path>>(path: P) -> Self;
   fn path(&self) -> &Path;
@@ -39,7 +94,7 @@ pub trait Directory {
   fn browse(&mut self) -> native_dialog::Result<()> {
     let mut fd = native_dialog::FileDialog::default();
 
-    if self.path().exists() {
+    if self.exists() {
       fd = fd.set_location(self.path());
     }
 
@@ -59,26 +114,71 @@ pub trait Directory {
 
 directory!(MultiMCDirectory, CurseForgeDirectory);
 
-impl Default for MultiMCDirectory {
-  fn default() -> Self {
+impl MultiMCDirectory {
+  /// Hard-coded location used when nothing could be detected
+  pub fn fallback() -> PathBuf {
     #[cfg(windows)] let data = std::env::var("APPDATA").unwrap_or_default();
     #[cfg(windows)] let path = format!(r"{}\MultiMC\minecraft\Instances", data);
-    #[cfg(not(windows))] let home = std::env::var("HOME").unwrap_or_default();
-    #[cfg(not(windows))] let path = format!(r"{}/.local/share/MultiMC/minecraft/Instances", home);
+    #[cfg(not(windows))] let path = home().join(".local/share/MultiMC/minecraft/Instances");
 
-    Self { path: Path::new(&path).into() }
+    Path::new(&path).into()
   }
 }
 
-impl Default for CurseForgeDirectory {
-  fn default() -> Self {
+impl MultiMCDirectory {
+  /// Best detected instance directory for `target`
+  pub fn detect(target: Target) -> Self {
+    let path = crate::detect::target_candidates(target)
+      .into_iter()
+      .next()
+      .map(|it| it.path)
+      .unwrap_or_else(Self::fallback);
+
+    Self { path }
+  }
+}
+
+impl CurseForgeDirectory {
+  /// Hard-coded location used when nothing could be detected
+  pub fn fallback() -> PathBuf {
     #[cfg(windows)] let user = std::env::var("USERNAME").unwrap_or_default();
-    #[cfg(not(windows))] let user = std::env::var("HOME").unwrap_or_default();
     #[cfg(windows)] let path = format!(r"C:\Users\{}\curseforge\minecraft\Instances", user);
-    #[cfg(target_os = "macos")] let path = format!(r"{}/Documents/curseforge/minecraft/Instances", user);
-    // FIXME: This is to be updated once CurseForge supports linux
-    #[cfg(target_os = "linux")] let path = format!(r"{}/curseforge/minecraft/Instances", user);
+    #[cfg(target_os = "macos")] let path = home().join("Documents/curseforge/minecraft/Instances");
+    // CurseForge has no native Linux client, instances inside Wine prefixes are found by `crate::wine`
+    #[cfg(target_os = "linux")] let path = home().join("curseforge/minecraft/Instances");
+
+    Path::new(&path).into()
+  }
+}
 
-    Self { path: Path::new(&path).into() }
+impl CurseForgeDirectory {
+  /// Best detected instance directory for `source`
+  pub fn detect(source: Source) -> Self {
+    let path = crate::detect::source_candidates(source)
+      .into_iter()
+      .next()
+      .map(|it| it.path)
+      .unwrap_or_else(|| match source {
+        Source::CurseForge => Self::fallback(),
+        _ => source.launcher().default_dirs().into_iter().next().unwrap_or_default(),
+      });
+
+    Self { path }
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn names_must_be_a_single_directory() {
+    for name in ["Pack", "All the Mods 9", "Pack.v2", "..Pack"] {
+      assert!(check_name(name).is_ok(), "{}", name);
+    }
+
+    for name in ["", "  ", ".", "..", "../Pack", "a/b", "a\\b", "/abs", "C:\\Pack"] {
+      assert!(check_name(name).is_err(), "{}", name);
+    }
+  }
+}
@@ -0,0 +1,507 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, remove_file};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::cfg::read_cfg;
+use crate::cfpack::{PackFile, PackManifest, PackMinecraft, PackModLoader};
+use crate::directories::{Directory, MultiMCDirectory};
+use crate::hash::sha1_sha512;
+use crate::launcher::game_dir;
+use crate::link::{CFInstalledFile, get_cf_instance, Result};
+use crate::marker::MARKER_FILE;
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+use crate::mrpack::{is_allowed_download, ModrinthFile, ModrinthIndex, ModrinthResolver, profile_downloads};
+use crate::source::Source;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+  /// Zip with `manifest.json` and `overrides/`, as exported by CurseForge itself
+  CurseForge,
+  /// Modrinth `.mrpack` with `modrinth.index.json` and `overrides/`
+  Mrpack,
+  /// Instance zip that MultiMC and its forks can import directly
+  MultiMC,
+}
+
+impl ExportFormat {
+  pub const NAMES: [&'static str; 3] = ["curseforge", "mrpack", "mmc"];
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "curseforge" => Some(ExportFormat::CurseForge),
+      "mrpack" => Some(ExportFormat::Mrpack),
+      "mmc" => Some(ExportFormat::MultiMC),
+      _ => None,
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      ExportFormat::CurseForge | ExportFormat::MultiMC => "zip",
+      ExportFormat::Mrpack => "mrpack",
+    }
+  }
+}
+
+/// Decides which files of the game directory end up in an export, paths are relative and use `/`.
+/// `*` matches within a path segment, `**` across segments, and a plain directory matches everything in it
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+  /// Always exported, even when also excluded
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+}
+
+/// Left out of exports unless included again
+pub const DEFAULT_EXCLUDE: [&str; 9] = [
+  "saves",
+  "logs",
+  "crash-reports",
+  "screenshots",
+  "backups",
+  "downloads",
+  "usercache.json",
+  "usernamecache.json",
+  // Instance folders set aside while shared folders replace them
+  ".*.unshared",
+];
+
+fn glob(pattern: &[char], path: &[char]) -> bool {
+  match pattern {
+    [] => path.is_empty(),
+    ['*', '*', '/', rest @ ..] => (0..=path.len())
+      .filter(|&i| i == 0 || path[i - 1] == '/')
+      .any(|i| glob(rest, &path[i..])),
+    ['*', '*', rest @ ..] => (0..=path.len()).any(|i| glob(rest, &path[i..])),
+    ['*', rest @ ..] => (0..=path.len())
+      .take_while(|&i| i == 0 || path[i - 1] != '/')
+      .any(|i| glob(rest, &path[i..])),
+    ['?', rest @ ..] => matches!(path, [c, tail @ ..] if *c != '/' && glob(rest, tail)),
+    [p, rest @ ..] => matches!(path, [c, tail @ ..] if c == p && glob(rest, tail)),
+  }
+}
+
+/// Whether `path` matches `pattern` or lies inside a directory matching it
+pub fn matches(pattern: &str, path: &str) -> bool {
+  let path = path.chars().collect::<Vec<_>>();
+  let pattern = pattern.trim_end_matches('/');
+
+  glob(&pattern.chars().collect::<Vec<_>>(), &path)
+    || glob(&format!("{}/**", pattern).chars().collect::<Vec<_>>(), &path)
+}
+
+impl Filter {
+  /// Filter with [`DEFAULT_EXCLUDE`] added to `exclude`
+  pub fn new(include: Vec<String>, exclude: Vec<String>) -> Self {
+    let exclude = DEFAULT_EXCLUDE.iter().map(|it| it.to_string()).chain(exclude).collect();
+
+    Self { include, exclude }
+  }
+
+  pub fn allows(&self, path: &str) -> bool {
+    self.include.iter().any(|it| matches(it, path)) || !self.exclude.iter().any(|it| matches(it, path))
+  }
+}
+
+/// Outcome of an export
+#[derive(Debug, Clone)]
+pub struct ExportReport {
+  pub path: PathBuf,
+  /// Files referenced by the manifest instead of being packed
+  pub referenced: usize,
+  /// Files packed into the zip
+  pub packed: usize,
+  pub warnings: Vec<String>,
+}
+
+/// Directories CurseForge installs addons into
+pub const ADDON_DIRS: [&str; 3] = ["mods", "resourcepacks", "shaderpacks"];
+
+/// `path` relative to `dir` with `/` separators, the way zips and instance states store it
+pub fn relative_path(dir: &Path, path: &Path) -> Result<String> {
+  let relative = path.strip_prefix(dir)?
+    .components()
+    .map(|it| it.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/");
+
+  Ok(relative)
+}
+
+/// Files below `dir` that pass `filter`, relative to it with `/` separators
+pub fn files(dir: &Path, filter: &Filter) -> Result<Vec<String>> {
+  let mut files = Vec::new();
+
+  for entry in WalkDir::new(dir).min_depth(1).follow_links(true).sort_by_file_name() {
+    let entry = entry?;
+
+    if !entry.file_type().is_file() {
+      continue;
+    }
+
+    let relative = relative_path(dir, entry.path())?;
+
+    if relative != MARKER_FILE && filter.allows(&relative) {
+      files.push(relative);
+    }
+  }
+
+  Ok(files)
+}
+
+/// Files CurseForge installed into the instance along with their project ID, keyed by their path
+/// relative to the game directory. Empty for other sources, they don't keep track of where mods come from
+fn cf_addons(mp: &ModPack) -> Result<HashMap<String, (u64, CFInstalledFile)>> {
+  if mp.source != Source::CurseForge {
+    return Ok(HashMap::new());
+  }
+
+  let mut addons = HashMap::new();
+
+  for addon in get_cf_instance(mp)?.installed_addons {
+    let file = match addon.installed_file {
+      Some(file) => file,
+      None => continue,
+    };
+
+    for dir in ADDON_DIRS {
+      addons.insert(format!("{}/{}", dir, file.file_name), (addon.addon_id, file.clone()));
+    }
+  }
+
+  Ok(addons)
+}
+
+fn options() -> FileOptions {
+  FileOptions::default().compression_method(CompressionMethod::Deflated)
+}
+
+/// Something to put into a zip at the given path
+pub enum Entry {
+  Generated(String, Vec<u8>),
+  File(String, PathBuf),
+}
+
+/// `files` from `dir` as entries below `prefix`
+pub fn entries(prefix: &str, dir: &Path, files: &[String]) -> Vec<Entry> {
+  files.iter()
+    .map(|it| Entry::File(format!("{}{}", prefix, it), dir.join(it)))
+    .collect()
+}
+
+/// Writes `entries` to a zip at `output`, the zip is removed again when anything fails
+pub fn write_zip(output: &Path, entries: Vec<Entry>) -> Result<()> {
+  let result = (|| -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(output)?);
+
+    for entry in entries {
+      match entry {
+        Entry::Generated(name, contents) => {
+          zip.start_file(name, options())?;
+          zip.write_all(&contents)?;
+        }
+        Entry::File(name, path) => {
+          zip.start_file(name, options())?;
+          std::io::copy(&mut File::open(path)?, &mut zip)?;
+        }
+      }
+    }
+
+    zip.finish()?;
+
+    Ok(())
+  })();
+
+  if result.is_err() {
+    let _ = remove_file(output);
+  }
+
+  result
+}
+
+/// Writes `mp` as a CurseForge modpack zip to `output`. Addons CurseForge knows the project and file of are
+/// listed in `manifest.json`, everything else that passes `filter` goes into `overrides/`
+pub fn export_curseforge(mp: &ModPack, output: &Path, filter: &Filter) -> Result<ExportReport> {
+  if mp.source != Source::CurseForge {
+    return Err(Box::from("Only CurseForge instances know the projects their mods come from"));
+  }
+
+  let cf_instance = get_cf_instance(mp)?;
+  let instance = mp.instance()?;
+  let addons = cf_addons(mp)?;
+  let mut files = Vec::new();
+  let mut overrides = Vec::new();
+
+  for file in self::files(&instance.game_dir, filter)? {
+    match addons.get(&file) {
+      Some((project_id, installed)) => files.push(PackFile { project_id: *project_id, file_id: installed.id, required: true }),
+      None if file == "minecraftinstance.json" => {}
+      None => overrides.push(file),
+    }
+  }
+
+  let manifest = PackManifest {
+    minecraft: PackMinecraft {
+      version: instance.mc_version.clone(),
+      mod_loaders: instance.loader.id()
+        .map(|id| PackModLoader { id, primary: true })
+        .into_iter()
+        .collect(),
+    },
+    manifest_type: String::from("minecraftModpack"),
+    manifest_version: 1,
+    name: instance.name.clone(),
+    version: cf_instance.manifest.as_ref().map(|it| it.version.clone()).unwrap_or_default(),
+    author: String::new(),
+    files,
+    overrides: String::from("overrides"),
+  };
+
+  let index = Entry::Generated(String::from("manifest.json"), serde_json::to_vec_pretty(&manifest)?);
+
+  write_zip(output, std::iter::once(index).chain(entries("overrides/", &instance.game_dir, &overrides)).collect())?;
+
+  Ok(ExportReport {
+    path: output.to_path_buf(),
+    referenced: manifest.files.len(),
+    packed: overrides.len(),
+    warnings: Vec::new(),
+  })
+}
+
+/// Writes `mp` as a Modrinth `.mrpack` to `output`. Addons are listed in `modrinth.index.json` with locally computed
+/// hashes when their download URL is on a host the format allows, which CurseForge's CDN isn't. Other addons are
+/// looked up by sha1, first in the Modrinth App profile and then with `resolver`, whatever isn't found is bundled
+/// into `overrides/` with a warning
+pub fn export_mrpack(
+  mp: &ModPack,
+  output: &Path,
+  filter: &Filter,
+  resolver: &dyn ModrinthResolver,
+) -> Result<ExportReport> {
+  let instance = mp.instance()?;
+  let addons = cf_addons(mp)?;
+  let version = instance.managed.as_ref().map(|it| it.version_name.clone()).unwrap_or_default();
+  let mut files = Vec::new();
+  let mut overrides = Vec::new();
+  let mut warnings = Vec::new();
+  let mut addon_files = Vec::new();
+
+  for file in self::files(&instance.game_dir, filter)? {
+    if file == mp.source.launcher().instance_file() {
+      continue;
+    }
+
+    let is_addon = file.split_once('/')
+      .map(|(dir, name)| ADDON_DIRS.contains(&dir) && !name.contains('/'))
+      .unwrap_or(false);
+
+    match is_addon {
+      true => {
+        let (sha1, sha512) = sha1_sha512(&instance.game_dir.join(&file))?;
+        let url = addons.get(&file).and_then(|(_, it)| it.download_url.clone());
+
+        addon_files.push((file, sha1, sha512, url));
+      }
+      false => overrides.push(file),
+    }
+  }
+
+  let mut known = match mp.source {
+    Source::Modrinth => profile_downloads(&mp.path().join(mp.source.launcher().instance_file())),
+    _ => HashMap::new(),
+  };
+
+  let unknown = addon_files.iter()
+    .filter(|(_, sha1, _, url)| !url.as_deref().map(is_allowed_download).unwrap_or(false) && !known.contains_key(sha1))
+    .map(|(_, sha1, _, _)| sha1.clone())
+    .collect::<Vec<_>>();
+
+  match resolver.resolve(&unknown) {
+    Ok(resolved) => known.extend(resolved),
+    Err(err) => warnings.push(format!("Couldn't look mods up on Modrinth: {}", err)),
+  }
+
+  for (file, sha1, sha512, url) in addon_files {
+    let url = url.filter(|it| is_allowed_download(it)).or_else(|| known.get(&sha1).cloned());
+
+    match url {
+      Some(url) => files.push(ModrinthFile {
+        hashes: BTreeMap::from([(String::from("sha1"), sha1), (String::from("sha512"), sha512)]),
+        downloads: vec![url],
+        file_size: instance.game_dir.join(&file).metadata()?.len(),
+        path: file,
+      }),
+      None => {
+        warnings.push(format!("{} wasn't found on Modrinth, bundling it into overrides", file));
+        overrides.push(file);
+      }
+    }
+  }
+
+  let index = ModrinthIndex {
+    format_version: 1,
+    game: String::from("minecraft"),
+    version_id: match version.is_empty() {
+      true => String::from("1.0.0"),
+      false => version,
+    },
+    name: instance.name.clone(),
+    summary: None,
+    files,
+    dependencies: ModrinthIndex::dependencies(&instance.mc_version, &instance.loader),
+  };
+
+  let referenced = index.files.len();
+  let index = Entry::Generated(String::from("modrinth.index.json"), serde_json::to_vec_pretty(&index)?);
+
+  write_zip(output, std::iter::once(index).chain(entries("overrides/", &instance.game_dir, &overrides)).collect())?;
+
+  Ok(ExportReport {
+    path: output.to_path_buf(),
+    referenced,
+    packed: overrides.len(),
+    warnings,
+  })
+}
+
+/// Icon file the instance's `iconKey` refers to, MultiMC imports it when it sits next to `instance.cfg`
+pub fn instance_icon(settings: &ApplicationSettings, mmc: &MultiMCDirectory, mmc_path: &Path) -> Option<PathBuf> {
+  let cfg = read_cfg(mmc_path.join("instance.cfg")).ok()?;
+  let key = cfg.get("iconKey")?;
+  let launcher = settings.target.launcher();
+  let icons_dir = launcher.icons_dir(&launcher.data_dir(mmc.path()));
+
+  icons_dir.read_dir().ok()?
+    .flatten()
+    .map(|it| it.path())
+    .find(|it| it.file_stem().map(|it| it == key.as_str()).unwrap_or(false))
+}
+
+/// Writes the instance `mp` is linked to as a zip MultiMC and its forks can import. The game directory
+/// symlink is resolved, so the zip contains the actual files that pass `filter`
+pub fn export_mmc(
+  settings: &ApplicationSettings,
+  mmc: &MultiMCDirectory,
+  mp: &ModPack,
+  output: &Path,
+  filter: &Filter,
+) -> Result<ExportReport> {
+  let mmc_path = mp.mmc_path(mmc).ok_or("This instance isn't linked")?;
+  let game_dir = game_dir(&mmc_path);
+  let game_dir_name = game_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+  let files = self::files(&game_dir, filter)?
+    .into_iter()
+    .filter(|it| it.as_str() != mp.source.launcher().instance_file())
+    .collect::<Vec<_>>();
+
+  let mut zip_entries = vec![
+    Entry::File(String::from("instance.cfg"), mmc_path.join("instance.cfg")),
+    Entry::File(String::from("mmc-pack.json"), mmc_path.join("mmc-pack.json")),
+  ];
+
+  if let Some(icon) = instance_icon(settings, mmc, &mmc_path) {
+    zip_entries.push(Entry::File(icon.file_name().unwrap_or_default().to_string_lossy().to_string(), icon));
+  }
+
+  let packed = files.len() + zip_entries.len();
+
+  zip_entries.extend(entries(&format!("{}/", game_dir_name), &game_dir, &files));
+  write_zip(output, zip_entries)?;
+
+  Ok(ExportReport {
+    path: output.to_path_buf(),
+    referenced: 0,
+    packed,
+    warnings: Vec::new(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::RefCell;
+
+  use zip::ZipArchive;
+
+  use super::*;
+
+  /// Knows `b.jar`, and remembers what it was asked for
+  struct FakeResolver {
+    known: HashMap<String, String>,
+    asked: RefCell<Vec<String>>,
+  }
+
+  impl ModrinthResolver for FakeResolver {
+    fn resolve(&self, sha1s: &[String]) -> Result<HashMap<String, String>> {
+      self.asked.borrow_mut().extend(sha1s.iter().cloned());
+
+      Ok(sha1s.iter().filter_map(|it| Some((it.clone(), self.known.get(it)?.clone()))).collect())
+    }
+  }
+
+  #[test]
+  fn mrpack_lists_resolved_jars_and_bundles_the_rest() {
+    let dir = std::env::temp_dir().join(format!("export-{}-mrpack", std::process::id()));
+    let profile = dir.join("Pack");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    std::fs::create_dir_all(profile.join("mods")).unwrap();
+    std::fs::create_dir_all(profile.join("config")).unwrap();
+    std::fs::write(profile.join("mods/a.jar"), "a").unwrap();
+    std::fs::write(profile.join("mods/b.jar"), "b").unwrap();
+    std::fs::write(profile.join("mods/c.jar"), "c").unwrap();
+    std::fs::write(profile.join("config/x.toml"), "x").unwrap();
+
+    let sha1 = |name: &str| sha1_sha512(&profile.join("mods").join(name)).unwrap().0;
+    let a_url = "https://cdn.modrinth.com/data/AAAA/versions/1/a.jar";
+    let b_url = "https://cdn.modrinth.com/data/BBBB/versions/2/b.jar";
+
+    std::fs::write(profile.join("profile.json"), serde_json::json!({
+      "metadata": { "name": "Pack", "game_version": "1.20.1", "loader": "fabric", "loader_version": { "id": "0.14.21" } },
+      "projects": {
+        "mods/a.jar": { "metadata": { "type": "modrinth", "version": { "files": [{ "url": a_url, "hashes": { "sha1": sha1("a.jar") } }] } } },
+        "mods/c.jar": { "metadata": { "type": "unknown" } }
+      }
+    }).to_string()).unwrap();
+
+    let resolver = FakeResolver {
+      known: HashMap::from([(sha1("b.jar"), String::from(b_url))]),
+      asked: RefCell::new(Vec::new()),
+    };
+
+    let mp = ModPack::new(profile.clone(), Source::Modrinth);
+    let output = dir.join("Pack.mrpack");
+    let report = export_mrpack(&mp, &output, &Filter::new(Vec::new(), Vec::new()), &resolver).unwrap();
+
+    let mut asked = resolver.asked.into_inner();
+    let mut expected = vec![sha1("b.jar"), sha1("c.jar")];
+
+    asked.sort();
+    expected.sort();
+
+    assert_eq!(asked, expected, "asked about a jar the profile already knew");
+    assert_eq!((report.referenced, report.packed), (2, 2));
+    assert_eq!(report.warnings, ["mods/c.jar wasn't found on Modrinth, bundling it into overrides"]);
+
+    let mut zip = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+    let index = serde_json::from_reader::<_, ModrinthIndex>(zip.by_name("modrinth.index.json").unwrap()).unwrap();
+    let mut listed = index.files.iter().map(|it| (it.path.as_str(), it.downloads[0].as_str())).collect::<Vec<_>>();
+
+    listed.sort();
+
+    assert_eq!(listed, [("mods/a.jar", a_url), ("mods/b.jar", b_url)]);
+    assert_eq!(index.files[0].hashes.len(), 2);
+    assert_eq!(index.dependencies.get("fabric-loader").map(String::as_str), Some("0.14.21"));
+    assert!(zip.by_name("overrides/mods/c.jar").is_ok());
+    assert!(zip.by_name("overrides/config/x.toml").is_ok());
+    assert!(zip.by_name("overrides/mods/a.jar").is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,112 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use sha1::{Digest, Sha1};
+use sha2::Sha512;
+
+/// Hex encoded sha1 of a file
+pub fn sha1(path: &Path) -> std::io::Result<String> {
+  let mut file = BufReader::new(File::open(path)?);
+  let mut sha1 = Sha1::new();
+  let mut buf = [0; 64 * 1024];
+
+  loop {
+    let read = file.read(&mut buf)?;
+
+    if read == 0 {
+      return Ok(format!("{:x}", sha1.finalize()));
+    }
+
+    sha1.update(&buf[..read]);
+  }
+}
+
+/// Hex encoded sha1 and sha512 of a file, read in one pass
+pub fn sha1_sha512(path: &Path) -> std::io::Result<(String, String)> {
+  let mut file = BufReader::new(File::open(path)?);
+  let mut sha1 = Sha1::new();
+  let mut sha512 = Sha512::new();
+  let mut buf = [0; 64 * 1024];
+
+  loop {
+    let read = file.read(&mut buf)?;
+
+    if read == 0 {
+      break;
+    }
+
+    sha1.update(&buf[..read]);
+    sha512.update(&buf[..read]);
+  }
+
+  Ok((format!("{:x}", sha1.finalize()), format!("{:x}", sha512.finalize())))
+}
+
+/// Fingerprint CurseForge records for addon files, 32 bit MurmurHash2 with seed 1 over the file
+/// with all whitespace bytes (tab, newline, carriage return and space) removed
+pub fn cf_fingerprint(path: &Path) -> std::io::Result<u32> {
+  const M: u32 = 0x5bd1e995;
+
+  let mut bytes = Vec::new();
+  BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+  bytes.retain(|it| !matches!(it, 9 | 10 | 13 | 32));
+
+  let mut hash = 1 ^ bytes.len() as u32;
+  let mut chunks = bytes.chunks_exact(4);
+
+  for chunk in &mut chunks {
+    let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+
+    k = k.wrapping_mul(M);
+    k ^= k >> 24;
+    k = k.wrapping_mul(M);
+
+    hash = hash.wrapping_mul(M) ^ k;
+  }
+
+  let rest = chunks.remainder();
+
+  if !rest.is_empty() {
+    for (index, byte) in rest.iter().enumerate() {
+      hash ^= (*byte as u32) << (8 * index);
+    }
+
+    hash = hash.wrapping_mul(M);
+  }
+
+  hash ^= hash >> 13;
+  hash = hash.wrapping_mul(M);
+  hash ^= hash >> 15;
+
+  Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fingerprint(name: &str, bytes: &[u8]) -> u32 {
+    let path = std::env::temp_dir().join(format!("cf_fingerprint-{}-{}", std::process::id(), name));
+    std::fs::write(&path, bytes).unwrap();
+
+    let fingerprint = cf_fingerprint(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    fingerprint
+  }
+
+  /// Expected values come from the reference MurmurHash2 C implementation with seed 1 over the same bytes
+  /// with tab, newline, carriage return and space stripped
+  #[test]
+  fn cf_fingerprint_known_answers() {
+    assert_eq!(fingerprint("empty", b""), 1540447798);
+    assert_eq!(fingerprint("one", b"a"), 626045324);
+    assert_eq!(fingerprint("two", b"ab"), 1692487918);
+    assert_eq!(fingerprint("three", b"abc"), 1621425345);
+    assert_eq!(fingerprint("four", b"abcd"), 3376380438);
+    assert_eq!(fingerprint("text", b"Hello, World!\n"), 1961219979);
+    assert_eq!(fingerprint("source", b"public class Foo {\r\n\tint x = 1;\r\n}\r\n"), 2244615415);
+    assert_eq!(fingerprint("binary", b"\xff\xfe\x80\x7f\xc3\xa9 \xe2\x82"), 2660074059);
+  }
+}
@@ -0,0 +1,320 @@
+use std::fs::{copy, create_dir_all, File, remove_dir_all};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::cfpack::{PackFile, PackManifest};
+use crate::directories::{check_name, Directory, MultiMCDirectory};
+use crate::link::{gen_mmc_instance_cfg, gen_mmc_pack_json, Result, write_managed_pack_keys};
+use crate::misc::ApplicationSettings;
+use crate::source::SourceInstance;
+
+/// Provides the jar for a file listed in a pack manifest
+pub trait ModFetcher {
+  /// Path to a local copy of `file`, `None` when it isn't available
+  fn fetch(&self, file: &PackFile) -> Result<Option<PathBuf>>;
+}
+
+/// Never finds anything, every file ends up reported as missing
+pub struct NoFetcher;
+
+impl ModFetcher for NoFetcher {
+  fn fetch(&self, _file: &PackFile) -> Result<Option<PathBuf>> {
+    Ok(None)
+  }
+}
+
+/// Looks files up in a local directory, laid out as `<projectID>/<fileID>/<file>.jar`,
+/// `<fileID>/<file>.jar` or `<projectID>-<fileID>.jar`
+pub struct CacheFetcher {
+  pub dir: PathBuf,
+}
+
+fn first_file(dir: &Path) -> Option<PathBuf> {
+  dir.read_dir().ok()?
+    .flatten()
+    .map(|it| it.path())
+    .find(|it| it.is_file())
+}
+
+impl ModFetcher for CacheFetcher {
+  fn fetch(&self, file: &PackFile) -> Result<Option<PathBuf>> {
+    let project = file.project_id.to_string();
+    let id = file.file_id.to_string();
+    let flat = self.dir.join(format!("{}-{}.jar", project, id));
+
+    if flat.is_file() {
+      return Ok(Some(flat));
+    }
+
+    Ok(first_file(&self.dir.join(&project).join(&id)).or_else(|| first_file(&self.dir.join(&id))))
+  }
+}
+
+/// Outcome of [`import`]
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+  pub name: String,
+  pub path: PathBuf,
+  pub extracted: usize,
+  pub fetched: usize,
+  /// Files the fetcher couldn't provide
+  pub missing: Vec<PackFile>,
+}
+
+pub fn read_manifest(zip: &mut ZipArchive<File>) -> Result<PackManifest> {
+  let manifest = zip.by_name("manifest.json").map_err(|_| "Not a CurseForge modpack, no manifest.json")?;
+
+  Ok(serde_json::from_reader(manifest)?)
+}
+
+/// Extracts everything below `prefix/` in the zip into `to`, returns the number of files extracted
+pub fn extract_dir(zip: &mut ZipArchive<File>, prefix: &str, to: &Path) -> Result<usize> {
+  let mut extracted = 0;
+
+  for index in 0..zip.len() {
+    let mut entry = zip.by_index(index)?;
+    let relative = match entry.enclosed_name().and_then(|it| it.strip_prefix(prefix).ok()) {
+      // `enclosed_name` still lets `prefix/../x` through, which would land next to `to`
+      Some(relative) if relative.components().all(|it| matches!(it, Component::Normal(_)))
+        && relative.components().next().is_some() => relative.to_path_buf(),
+      _ => continue,
+    };
+
+    let path = to.join(relative);
+
+    if entry.is_dir() {
+      create_dir_all(&path)?;
+      continue;
+    }
+
+    if let Some(parent) = path.parent() {
+      create_dir_all(parent)?;
+    }
+
+    std::io::copy(&mut entry, &mut File::create(&path)?)?;
+    extracted += 1;
+  }
+
+  Ok(extracted)
+}
+
+/// Creates a new instance from a CurseForge modpack zip, `name` overrides the pack's name
+pub fn import(
+  settings: &ApplicationSettings,
+  mmc: &MultiMCDirectory,
+  zip_path: &Path,
+  name: Option<&str>,
+  fetcher: &dyn ModFetcher,
+) -> Result<ImportReport> {
+  let launcher = settings.target.launcher();
+  let mut zip = ZipArchive::new(File::open(zip_path)?)?;
+  let manifest = read_manifest(&mut zip)?;
+  let name = name.map(str::to_string).unwrap_or_else(|| manifest.name.clone());
+
+  check_name(&name)?;
+
+  let path = mmc.path().join(&name);
+
+  if path.exists() {
+    return Err(Box::from("A folder with that name already exists"));
+  }
+
+  let game_dir = path.join(launcher.game_dir_name());
+  let instance = SourceInstance {
+    name: name.clone(),
+    mc_version: manifest.minecraft.version.clone(),
+    loader: manifest.loader(),
+    game_dir: game_dir.clone(),
+    icon: None,
+    managed: None,
+  };
+
+  let result = (|| -> Result<ImportReport> {
+    create_dir_all(&game_dir)?;
+
+    File::create(path.join("instance.cfg"))?
+      .write_all(gen_mmc_instance_cfg(launcher, &instance, "default").as_bytes())?;
+    File::create(path.join("mmc-pack.json"))?
+      .write_all(serde_json::to_string_pretty(&gen_mmc_pack_json(&instance))?.as_bytes())?;
+    write_managed_pack_keys(launcher, &path, &instance)?;
+
+    let extracted = extract_dir(&mut zip, &manifest.overrides, &game_dir)?;
+    let mods = game_dir.join("mods");
+    let mut fetched = 0;
+    let mut missing = Vec::new();
+
+    create_dir_all(&mods)?;
+
+    for file in &manifest.files {
+      match fetcher.fetch(file)? {
+        Some(jar) => {
+          copy(&jar, mods.join(jar.file_name().unwrap_or_default()))?;
+          fetched += 1;
+        }
+        None => missing.push(file.clone()),
+      }
+    }
+
+    Ok(ImportReport { name: name.clone(), path: path.clone(), extracted, fetched, missing })
+  })();
+
+  if result.is_err() {
+    let _ = remove_dir_all(&path);
+  }
+
+  result
+}
+
+/// Fetcher to use for the configured mod cache
+pub fn fetcher(cache: Option<&str>) -> Box<dyn ModFetcher + Send> {
+  match cache {
+    Some(dir) => Box::new(CacheFetcher { dir: PathBuf::from(dir) }),
+    None => Box::new(NoFetcher),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use zip::write::FileOptions;
+  use zip::ZipWriter;
+
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("import-{}-{}", std::process::id(), name));
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+
+    for (name, contents) in entries {
+      zip.start_file(*name, FileOptions::default()).unwrap();
+      zip.write_all(contents.as_bytes()).unwrap();
+    }
+
+    zip.finish().unwrap();
+  }
+
+  fn manifest(name: &str) -> String {
+    serde_json::json!({
+      "minecraft": { "version": "1.20.1", "modLoaders": [{ "id": "forge-47.1.0", "primary": true }] },
+      "manifestType": "minecraftModpack",
+      "manifestVersion": 1,
+      "name": name,
+      "files": [{ "projectID": 1, "fileID": 10 }, { "projectID": 2, "fileID": 20 }, { "projectID": 3, "fileID": 30 }],
+      "overrides": "overrides"
+    }).to_string()
+  }
+
+  #[test]
+  fn extract_dir_stays_inside_the_target() {
+    let dir = temp_dir("slip");
+    let zip_path = dir.join("pack.zip");
+
+    write_zip(&zip_path, &[
+      ("overrides/config/a.toml", "a"),
+      ("overrides/../../escaped.txt", "parent"),
+      ("/absolute.txt", "absolute"),
+      ("overrides/../overrides/b.txt", "b"),
+      ("other/c.txt", "c"),
+    ]);
+
+    let mut zip = ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+    let to = dir.join("out/game");
+    let extracted = extract_dir(&mut zip, "overrides", &to).unwrap();
+
+    assert_eq!(extracted, 1);
+    assert_eq!(std::fs::read_to_string(to.join("config/a.toml")).unwrap(), "a");
+    assert!(!dir.join("escaped.txt").exists());
+    assert!(!dir.join("out/escaped.txt").exists());
+    assert!(!dir.join("out/overrides/b.txt").exists());
+    assert!(!to.join("c.txt").exists());
+
+    remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn cache_fetcher_finds_every_layout() {
+    let dir = temp_dir("cache");
+    let file = |project_id, file_id| PackFile { project_id, file_id, required: true };
+
+    create_dir_all(dir.join("1/10")).unwrap();
+    create_dir_all(dir.join("20")).unwrap();
+    std::fs::write(dir.join("1/10/a.jar"), "a").unwrap();
+    std::fs::write(dir.join("20/b.jar"), "b").unwrap();
+    std::fs::write(dir.join("3-30.jar"), "c").unwrap();
+
+    let fetcher = CacheFetcher { dir: dir.clone() };
+
+    assert_eq!(fetcher.fetch(&file(1, 10)).unwrap(), Some(dir.join("1/10/a.jar")));
+    assert_eq!(fetcher.fetch(&file(2, 20)).unwrap(), Some(dir.join("20/b.jar")));
+    assert_eq!(fetcher.fetch(&file(3, 30)).unwrap(), Some(dir.join("3-30.jar")));
+    assert_eq!(fetcher.fetch(&file(4, 40)).unwrap(), None);
+    assert_eq!(NoFetcher.fetch(&file(1, 10)).unwrap(), None);
+
+    remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn import_extracts_overrides_and_fetches_mods() {
+    let dir = temp_dir("import");
+    let zip_path = dir.join("pack.zip");
+    let manifest = manifest("Pack");
+
+    write_zip(&zip_path, &[("manifest.json", &manifest), ("overrides/options.txt", "fov:70")]);
+    create_dir_all(dir.join("cache/1/10")).unwrap();
+    std::fs::write(dir.join("cache/1/10/a.jar"), "a").unwrap();
+    std::fs::write(dir.join("cache/2-20.jar"), "b").unwrap();
+    create_dir_all(dir.join("instances")).unwrap();
+
+    let settings = ApplicationSettings::default();
+    let mmc = MultiMCDirectory::new(dir.join("instances"));
+    let fetcher = CacheFetcher { dir: dir.join("cache") };
+    let report = import(&settings, &mmc, &zip_path, None, &fetcher).unwrap();
+    let game_dir = report.path.join(settings.target.launcher().game_dir_name());
+
+    assert_eq!(report.path, dir.join("instances/Pack"));
+    assert_eq!((report.extracted, report.fetched), (1, 2));
+    assert_eq!(report.missing.iter().map(|it| it.project_id).collect::<Vec<_>>(), [3]);
+    assert_eq!(std::fs::read_to_string(game_dir.join("options.txt")).unwrap(), "fov:70");
+    assert!(game_dir.join("mods/a.jar").is_file());
+    assert!(game_dir.join("mods/2-20.jar").is_file());
+    assert!(report.path.join("instance.cfg").is_file());
+    assert!(report.path.join("mmc-pack.json").is_file());
+
+    assert!(import(&settings, &mmc, &zip_path, None, &NoFetcher).is_err(), "imported over an existing instance");
+
+    remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn bad_pack_names_are_rejected() {
+    let dir = temp_dir("names");
+    let settings = ApplicationSettings::default();
+    let mmc = MultiMCDirectory::new(dir.join("instances"));
+
+    create_dir_all(dir.join("instances")).unwrap();
+
+    for name in ["../escaped", "/absolute", "a/b", ".."] {
+      let zip_path = dir.join("pack.zip");
+
+      write_zip(&zip_path, &[("manifest.json", &manifest(name))]);
+
+      assert!(import(&settings, &mmc, &zip_path, None, &NoFetcher).is_err(), "{}", name);
+    }
+
+    let zip_path = dir.join("pack.zip");
+
+    assert!(import(&settings, &mmc, &zip_path, Some("../escaped"), &NoFetcher).is_err());
+    assert!(!dir.join("escaped").exists());
+    assert!(!Path::new("/absolute").exists());
+    assert_eq!(dir.join("instances").read_dir().unwrap().count(), 0);
+
+    remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Formatter;
+use std::path::{Path, PathBuf};
+
+use crate::jar::{JarMod, read_jar};
+use crate::link::{get_cf_instance, Result};
+use crate::modpack::ModPack;
+use crate::source::Source;
+
+/// Dependency type CurseForge uses for required dependencies
+const REQUIRED_DEPENDENCY: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseType {
+  Release,
+  Beta,
+  Alpha,
+}
+
+impl ReleaseType {
+  fn from_id(id: u8) -> Option<Self> {
+    match id {
+      1 => Some(ReleaseType::Release),
+      2 => Some(ReleaseType::Beta),
+      3 => Some(ReleaseType::Alpha),
+      _ => None,
+    }
+  }
+}
+
+impl std::fmt::Display for ReleaseType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      ReleaseType::Release => "release",
+      ReleaseType::Beta => "beta",
+      ReleaseType::Alpha => "alpha",
+    })
+  }
+}
+
+/// A mod in an instance's `mods` folder, or an addon CurseForge thinks is installed
+#[derive(Debug, Clone)]
+pub struct ModEntry {
+  /// Display name for addons, the file name otherwise
+  pub name: String,
+  pub file_name: String,
+  pub project_id: Option<u64>,
+  pub file_id: Option<u64>,
+  pub release_type: Option<ReleaseType>,
+  /// Names of required dependencies, project IDs for addons that aren't installed and mod IDs for jars
+  pub dependencies: Vec<String>,
+  /// In `mods` but not in CurseForge's addon list
+  pub manual: bool,
+  /// Read from the jar itself, only for mods CurseForge doesn't know about
+  pub mods: Vec<JarMod>,
+  /// CurseForge lists it but the file is gone
+  pub missing: bool,
+}
+
+impl std::fmt::Display for ModEntry {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.name)?;
+
+    if self.name != self.file_name {
+      write!(f, " ({})", self.file_name)?;
+    }
+
+    if let Some(release_type) = self.release_type.filter(|it| *it != ReleaseType::Release) {
+      write!(f, " [{}]", release_type)?;
+    }
+
+    if self.manual {
+      f.write_str(" [manually added]")?;
+    }
+
+    if self.missing {
+      f.write_str(" [missing]")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Jar files in `dir`, including disabled ones
+fn mod_files(dir: &Path) -> Vec<String> {
+  let mut files = dir.read_dir()
+    .map(|it| it.flatten()
+      .filter(|it| it.path().is_file())
+      .map(|it| it.file_name().to_string_lossy().to_string())
+      .filter(|it| it.ends_with(".jar") || it.ends_with(".jar.disabled"))
+      .collect::<Vec<_>>()
+    )
+    .unwrap_or_default();
+
+  files.sort();
+
+  files
+}
+
+/// Where the jar CurseForge calls `file_name` is in `dir`, the `.disabled` copy when the mod was turned off
+pub fn jar_path(dir: &Path, file_name: &str) -> PathBuf {
+  let disabled = dir.join(format!("{}.disabled", file_name));
+
+  match !dir.join(file_name).is_file() && disabled.is_file() {
+    true => disabled,
+    false => dir.join(file_name),
+  }
+}
+
+/// Entry for a jar CurseForge doesn't know about, described by its own metadata
+fn unknown(dir: &Path, file_name: String, manual: bool) -> ModEntry {
+  let mods = read_jar(&dir.join(&file_name)).unwrap_or_default();
+  let name = mods.first()
+    .map(|it| it.name.clone())
+    .filter(|it| !it.is_empty())
+    .unwrap_or_else(|| file_name.clone());
+  let dependencies = mods.iter()
+    .flat_map(|it| &it.dependencies)
+    .filter(|it| it.required)
+    .map(|it| it.id.clone())
+    .collect();
+
+  ModEntry {
+    name,
+    file_name,
+    project_id: None,
+    file_id: None,
+    release_type: None,
+    dependencies,
+    manual,
+    mods,
+    missing: false,
+  }
+}
+
+/// Every mod of `mp`, sorted by name. Only CurseForge keeps an addon list, so for other sources
+/// this is just the content of the `mods` folder and nothing is flagged as manually added
+pub fn inventory(mp: &ModPack) -> Result<Vec<ModEntry>> {
+  let dir = mp.instance()?.game_dir.join("mods");
+  let files = mod_files(&dir);
+
+  if mp.source != Source::CurseForge {
+    return Ok(files.into_iter().map(|file_name| unknown(&dir, file_name, false)).collect());
+  }
+
+  let addons = get_cf_instance(mp)?.installed_addons;
+  let name_of = |project_id: u64| {
+    addons.iter()
+      .find(|it| it.addon_id == project_id)
+      .map(|it| it.name.clone())
+      .unwrap_or_else(|| project_id.to_string())
+  };
+
+  let mut entries = addons.iter()
+    .filter_map(|addon| {
+      let file = addon.installed_file.as_ref()?;
+      let is_mod = file.file_name.ends_with(".jar");
+      let present = files.iter().any(|it| it == &file.file_name || *it == format!("{}.disabled", file.file_name));
+
+      // Resource packs and shaders live elsewhere and aren't part of the mod list
+      if !is_mod && !present {
+        return None;
+      }
+
+      Some(ModEntry {
+        name: match addon.name.is_empty() {
+          true => file.display_name.clone(),
+          false => addon.name.clone(),
+        },
+        file_name: file.file_name.clone(),
+        project_id: Some(addon.addon_id),
+        file_id: Some(file.id),
+        release_type: ReleaseType::from_id(file.release_type),
+        dependencies: file.dependencies.iter()
+          .filter(|it| it.kind == REQUIRED_DEPENDENCY)
+          .map(|it| name_of(it.addon_id))
+          .collect(),
+        manual: false,
+        mods: Vec::new(),
+        missing: !present,
+      })
+    })
+    .collect::<Vec<_>>();
+
+  for file_name in files {
+    let known = entries.iter().any(|it| file_name == it.file_name || file_name == format!("{}.disabled", it.file_name));
+
+    if !known {
+      entries.push(unknown(&dir, file_name, true));
+    }
+  }
+
+  entries.sort_by_key(|it| it.name.to_lowercase());
+
+  Ok(entries)
+}
@@ -0,0 +1,502 @@
+use std::fmt::Formatter;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::link::Result;
+
+/// Loader a jar's metadata was written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JarLoader {
+  Fabric,
+  Quilt,
+  Forge,
+  NeoForge,
+}
+
+impl std::fmt::Display for JarLoader {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      JarLoader::Fabric => "Fabric",
+      JarLoader::Quilt => "Quilt",
+      JarLoader::Forge => "Forge",
+      JarLoader::NeoForge => "NeoForge",
+    })
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JarDependency {
+  pub id: String,
+  /// Version range as written by the mod, empty when any version works
+  pub versions: String,
+  pub required: bool,
+}
+
+/// A mod declared in a jar's metadata, one jar can declare several
+#[derive(Debug, Clone)]
+pub struct JarMod {
+  pub id: String,
+  pub name: String,
+  pub version: String,
+  pub loader: JarLoader,
+  pub dependencies: Vec<JarDependency>,
+  /// Other mod ids this mod satisfies, declared aliases and mods bundled as nested jars
+  pub provides: Vec<String>,
+  /// `client`, `server` or `both`, only Fabric and Quilt declare it
+  pub side: Option<String>,
+}
+
+fn side_of(environment: &str) -> Option<String> {
+  match environment {
+    "client" => Some(String::from("client")),
+    "server" | "dedicated_server" => Some(String::from("server")),
+    "*" => Some(String::from("both")),
+    _ => None,
+  }
+}
+
+/// Metadata of a single jar in a `mods` folder
+#[derive(Debug, Clone)]
+pub struct JarInfo {
+  pub path: PathBuf,
+  /// Empty when the jar has no metadata this application understands
+  pub mods: Vec<JarMod>,
+}
+
+/// How deep nested jars are followed, libraries sometimes bundle their own dependencies
+const MAX_NESTING: usize = 3;
+
+fn read_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Option<String> {
+  let mut contents = String::new();
+
+  zip.by_name(name).ok()?.read_to_string(&mut contents).ok()?;
+
+  Some(contents)
+}
+
+fn str_of(value: &Value, key: &str) -> String {
+  value.get(key).and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+/// Version ranges are either a string or a list of alternatives
+fn versions_of(value: &Value) -> String {
+  match value {
+    Value::String(it) => it.clone(),
+    Value::Array(it) => it.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(" || "),
+    _ => String::new(),
+  }
+}
+
+fn fabric(json: &str) -> Option<Vec<JarMod>> {
+  let json = serde_json::from_str::<Value>(json).ok()?;
+  let dependencies = |key: &str, required: bool| {
+    json.get(key)
+      .and_then(Value::as_object)
+      .map(|it| it.iter()
+        .map(|(id, versions)| JarDependency { id: id.clone(), versions: versions_of(versions), required })
+        .collect::<Vec<_>>()
+      )
+      .unwrap_or_default()
+  };
+
+  Some(vec![JarMod {
+    id: str_of(&json, "id"),
+    name: str_of(&json, "name"),
+    version: str_of(&json, "version"),
+    loader: JarLoader::Fabric,
+    dependencies: dependencies("depends", true).into_iter().chain(dependencies("recommends", false)).collect(),
+    provides: json.get("provides")
+      .and_then(Value::as_array)
+      .map(|it| it.iter().filter_map(Value::as_str).map(str::to_string).collect())
+      .unwrap_or_default(),
+    side: side_of(&str_of(&json, "environment")),
+  }])
+}
+
+fn quilt(json: &str) -> Option<Vec<JarMod>> {
+  let json = serde_json::from_str::<Value>(json).ok()?;
+  let loader = json.get("quilt_loader")?;
+  let dependencies = loader.get("depends")
+    .and_then(Value::as_array)
+    .map(|it| it.iter()
+      .map(|it| match it {
+        Value::String(id) => JarDependency { id: id.clone(), versions: String::new(), required: true },
+        _ => JarDependency {
+          id: str_of(it, "id"),
+          versions: it.get("versions").map(versions_of).unwrap_or_default(),
+          required: !it.get("optional").and_then(Value::as_bool).unwrap_or(false),
+        },
+      })
+      .collect()
+    )
+    .unwrap_or_default();
+
+  Some(vec![JarMod {
+    id: str_of(loader, "id"),
+    name: loader.get("metadata").map(|it| str_of(it, "name")).unwrap_or_default(),
+    version: str_of(loader, "version"),
+    loader: JarLoader::Quilt,
+    dependencies,
+    provides: loader.get("provides")
+      .and_then(Value::as_array)
+      .map(|it| it.iter()
+        .map(|it| match it {
+          Value::String(id) => id.clone(),
+          _ => str_of(it, "id"),
+        })
+        .collect()
+      )
+      .unwrap_or_default(),
+    side: json.get("minecraft").and_then(|it| side_of(&str_of(it, "environment"))),
+  }])
+}
+
+/// `Implementation-Version` from the jar manifest, what `${file.jarVersion}` in `mods.toml` refers to
+fn jar_version<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Option<String> {
+  read_entry(zip, "META-INF/MANIFEST.MF")?
+    .lines()
+    .find_map(|it| it.strip_prefix("Implementation-Version:"))
+    .map(|it| it.trim().to_string())
+}
+
+fn mods_toml(toml: &str, loader: JarLoader, jar_version: Option<String>) -> Option<Vec<JarMod>> {
+  let toml = toml.parse::<toml::Value>().ok()?;
+  let str_of = |value: &toml::Value, key: &str| {
+    value.get(key).and_then(toml::Value::as_str).unwrap_or_default().to_string()
+  };
+
+  let mods = toml.get("mods")?.as_array()?.iter()
+    .map(|it| {
+      let id = str_of(it, "modId");
+      let version = match str_of(it, "version") {
+        version if version == "${file.jarVersion}" => jar_version.clone().unwrap_or(version),
+        version => version,
+      };
+
+      let dependencies = toml.get("dependencies")
+        .and_then(|it| it.get(&id))
+        .and_then(toml::Value::as_array)
+        .map(|it| it.iter()
+          .map(|it| JarDependency {
+            id: str_of(it, "modId"),
+            versions: str_of(it, "versionRange"),
+            // Forge uses `mandatory`, NeoForge `type = "required"`
+            required: it.get("mandatory").and_then(toml::Value::as_bool)
+              .unwrap_or_else(|| str_of(it, "type").eq_ignore_ascii_case("required")),
+          })
+          .collect()
+        )
+        .unwrap_or_default();
+
+      JarMod { name: str_of(it, "displayName"), id, version, loader, dependencies, provides: Vec::new(), side: None }
+    })
+    .collect();
+
+  Some(mods)
+}
+
+/// Forge before 1.13, either a list of mods or `{ "modList": [...] }`
+fn mcmod_info(json: &str) -> Option<Vec<JarMod>> {
+  let json = serde_json::from_str::<Value>(json).ok()?;
+  let list = match &json {
+    Value::Array(it) => it,
+    _ => json.get("modList")?.as_array()?,
+  };
+
+  let mods = list.iter()
+    .map(|it| {
+      let dependencies = it.get("requiredMods")
+        .and_then(Value::as_array)
+        .map(|it| it.iter()
+          .filter_map(Value::as_str)
+          .map(|it| {
+            let (id, versions) = it.split_once('@').unwrap_or((it, ""));
+
+            JarDependency { id: id.to_string(), versions: versions.to_string(), required: true }
+          })
+          .collect()
+        )
+        .unwrap_or_default();
+
+      JarMod {
+        id: str_of(it, "modid"),
+        name: str_of(it, "name"),
+        version: str_of(it, "version"),
+        loader: JarLoader::Forge,
+        dependencies,
+        provides: Vec::new(),
+        side: None,
+      }
+    })
+    .collect();
+
+  Some(mods)
+}
+
+/// Mods declared by every kind of metadata in `zip`, multi-loader jars declare the same mod several times
+fn read_zip<R: Read + Seek>(zip: &mut ZipArchive<R>, depth: usize) -> Vec<JarMod> {
+  let mut mods = Vec::new();
+
+  if let Some(json) = read_entry(zip, "fabric.mod.json") {
+    mods.extend(fabric(&json).unwrap_or_default());
+  }
+
+  if let Some(json) = read_entry(zip, "quilt.mod.json") {
+    mods.extend(quilt(&json).unwrap_or_default());
+  }
+
+  for (file, loader) in [("META-INF/neoforge.mods.toml", JarLoader::NeoForge), ("META-INF/mods.toml", JarLoader::Forge)] {
+    if let Some(toml) = read_entry(zip, file) {
+      let jar_version = jar_version(zip);
+
+      mods.extend(mods_toml(&toml, loader, jar_version).unwrap_or_default());
+    }
+  }
+
+  if let Some(json) = read_entry(zip, "mcmod.info") {
+    mods.extend(mcmod_info(&json).unwrap_or_default());
+  }
+
+  if depth < MAX_NESTING && !mods.is_empty() {
+    let nested = nested_jars(zip)
+      .into_iter()
+      .filter_map(|it| ZipArchive::new(Cursor::new(it)).ok())
+      .flat_map(|mut it| read_zip(&mut it, depth + 1))
+      .flat_map(|it| std::iter::once(it.id).chain(it.provides))
+      .collect::<Vec<_>>();
+
+    for jar_mod in &mut mods {
+      jar_mod.provides.extend(nested.iter().cloned());
+    }
+  }
+
+  mods
+}
+
+/// Contents of jars bundled by Fabric, Quilt (`META-INF/jars`) and Forge's jar-in-jar (`META-INF/jarjar`)
+fn nested_jars<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Vec<Vec<u8>> {
+  let names = zip.file_names()
+    .filter(|it| it.starts_with("META-INF/jars/") || it.starts_with("META-INF/jarjar/"))
+    .filter(|it| it.ends_with(".jar"))
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+
+  names.iter()
+    .filter_map(|name| {
+      let mut contents = Vec::new();
+
+      zip.by_name(name).ok()?.read_to_end(&mut contents).ok()?;
+
+      Some(contents)
+    })
+    .collect()
+}
+
+/// Reads the mods declared in a jar, without loading anything from it
+pub fn read_jar(path: &Path) -> Result<Vec<JarMod>> {
+  let mut zip = ZipArchive::new(File::open(path)?)?;
+
+  Ok(read_zip(&mut zip, 0))
+}
+
+/// Metadata of every enabled jar in `dir`, jars that can't be opened are listed without mods
+pub fn read_mods_dir(dir: &Path) -> Vec<JarInfo> {
+  let mut jars = dir.read_dir()
+    .map(|it| it.flatten()
+      .map(|it| it.path())
+      .filter(|it| it.is_file() && it.extension().map(|it| it == "jar").unwrap_or(false))
+      .map(|path| JarInfo { mods: read_jar(&path).unwrap_or_default(), path })
+      .collect::<Vec<_>>()
+    )
+    .unwrap_or_default();
+
+  jars.sort_by(|a, b| a.path.cmp(&b.path));
+
+  jars
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use zip::write::FileOptions;
+  use zip::ZipWriter;
+
+  use super::*;
+
+  fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (name, contents) in entries {
+      zip.start_file(*name, FileOptions::default()).unwrap();
+      zip.write_all(contents).unwrap();
+    }
+
+    zip.finish().unwrap().into_inner()
+  }
+
+  fn read(bytes: Vec<u8>) -> Vec<JarMod> {
+    read_zip(&mut ZipArchive::new(Cursor::new(bytes)).unwrap(), 0)
+  }
+
+  fn dependency(id: &str, versions: &str, required: bool) -> JarDependency {
+    JarDependency { id: id.to_string(), versions: versions.to_string(), required }
+  }
+
+  #[test]
+  fn fabric_mod_json() {
+    let json = br#"{
+      "id": "sodium", "name": "Sodium", "version": "0.5.3", "environment": "client",
+      "depends": { "fabricloader": ">=0.12", "minecraft": ["1.20", "1.20.1"] },
+      "recommends": { "modmenu": "*" },
+      "provides": ["rubidium"]
+    }"#;
+
+    let mods = read(jar(&[("fabric.mod.json", json)]));
+
+    assert_eq!(mods.len(), 1);
+    assert_eq!((mods[0].id.as_str(), mods[0].name.as_str(), mods[0].version.as_str()), ("sodium", "Sodium", "0.5.3"));
+    assert_eq!(mods[0].loader, JarLoader::Fabric);
+    assert_eq!(mods[0].side.as_deref(), Some("client"));
+    assert_eq!(mods[0].provides, ["rubidium"]);
+    assert_eq!(mods[0].dependencies, [
+      dependency("fabricloader", ">=0.12", true),
+      dependency("minecraft", "1.20 || 1.20.1", true),
+      dependency("modmenu", "*", false),
+    ]);
+  }
+
+  #[test]
+  fn quilt_mod_json() {
+    let json = br#"{
+      "quilt_loader": {
+        "id": "qsl", "version": "6.1.0", "metadata": { "name": "Quilt Standard Libraries" },
+        "depends": ["quilt_loader", { "id": "minecraft", "versions": ">=1.20" }, { "id": "emi", "optional": true }],
+        "provides": ["qsl_base", { "id": "quilted_fabric_api" }]
+      },
+      "minecraft": { "environment": "*" }
+    }"#;
+
+    let mods = read(jar(&[("quilt.mod.json", json)]));
+
+    assert_eq!(mods.len(), 1);
+    assert_eq!((mods[0].id.as_str(), mods[0].name.as_str()), ("qsl", "Quilt Standard Libraries"));
+    assert_eq!(mods[0].loader, JarLoader::Quilt);
+    assert_eq!(mods[0].side.as_deref(), Some("both"));
+    assert_eq!(mods[0].provides, ["qsl_base", "quilted_fabric_api"]);
+    assert_eq!(mods[0].dependencies, [
+      dependency("quilt_loader", "", true),
+      dependency("minecraft", ">=1.20", true),
+      dependency("emi", "", false),
+    ]);
+  }
+
+  #[test]
+  fn forge_mods_toml_with_jar_version() {
+    let toml = br#"
+      modLoader = "javafml"
+      loaderVersion = "[47,)"
+
+      [[mods]]
+      modId = "create"
+      version = "${file.jarVersion}"
+      displayName = "Create"
+
+      [[mods]]
+      modId = "ponder"
+      version = "1.0"
+
+      [[dependencies.create]]
+      modId = "forge"
+      mandatory = true
+      versionRange = "[47,)"
+
+      [[dependencies.create]]
+      modId = "jei"
+      mandatory = false
+      versionRange = ""
+    "#;
+
+    let manifest = b"Manifest-Version: 1.0\r\nImplementation-Version: 0.5.1.f\r\n";
+    let mods = read(jar(&[("META-INF/mods.toml", toml), ("META-INF/MANIFEST.MF", manifest)]));
+
+    assert_eq!(mods.len(), 2);
+    assert_eq!((mods[0].id.as_str(), mods[0].name.as_str(), mods[0].version.as_str()), ("create", "Create", "0.5.1.f"));
+    assert_eq!(mods[0].loader, JarLoader::Forge);
+    assert_eq!(mods[0].dependencies, [dependency("forge", "[47,)", true), dependency("jei", "", false)]);
+    assert_eq!((mods[1].id.as_str(), mods[1].version.as_str()), ("ponder", "1.0"));
+    assert!(mods[1].dependencies.is_empty());
+
+    // Without a manifest the placeholder is all there is
+    let mods = read(jar(&[("META-INF/mods.toml", toml)]));
+
+    assert_eq!(mods[0].version, "${file.jarVersion}");
+  }
+
+  #[test]
+  fn neoforge_mods_toml() {
+    let toml = br#"
+      [[mods]]
+      modId = "jei"
+      version = "19.0.0"
+      displayName = "Just Enough Items"
+
+      [[dependencies.jei]]
+      modId = "neoforge"
+      type = "required"
+      versionRange = "[21,)"
+
+      [[dependencies.jei]]
+      modId = "emi"
+      type = "optional"
+    "#;
+
+    let mods = read(jar(&[("META-INF/neoforge.mods.toml", toml)]));
+
+    assert_eq!(mods.len(), 1);
+    assert_eq!(mods[0].loader, JarLoader::NeoForge);
+    assert_eq!(mods[0].name, "Just Enough Items");
+    assert_eq!(mods[0].dependencies, [dependency("neoforge", "[21,)", true), dependency("emi", "", false)]);
+  }
+
+  #[test]
+  fn mcmod_info_in_both_shapes() {
+    let list = br#"[{ "modid": "buildcraft", "name": "BuildCraft", "version": "7.1", "requiredMods": ["Forge@[10.13,)", "mj"] }]"#;
+    let wrapped = br#"{ "modListVersion": 2, "modList": [{ "modid": "ic2", "name": "IC2", "version": "2.2" }] }"#;
+
+    let mods = read(jar(&[("mcmod.info", list)]));
+
+    assert_eq!(mods.len(), 1);
+    assert_eq!((mods[0].id.as_str(), mods[0].version.as_str()), ("buildcraft", "7.1"));
+    assert_eq!(mods[0].loader, JarLoader::Forge);
+    assert_eq!(mods[0].dependencies, [dependency("Forge", "[10.13,)", true), dependency("mj", "", true)]);
+
+    let mods = read(jar(&[("mcmod.info", wrapped)]));
+
+    assert_eq!(mods.iter().map(|it| it.id.as_str()).collect::<Vec<_>>(), ["ic2"]);
+    assert!(read(jar(&[("mcmod.info", b"not json")])).is_empty());
+  }
+
+  #[test]
+  fn nested_jars_are_followed_up_to_max_nesting() {
+    let fabric = |id: &str| format!(r#"{{ "id": "{}", "version": "1" }}"#, id);
+    let mut inner = jar(&[("fabric.mod.json", fabric("level4").as_bytes())]);
+
+    for level in (1..MAX_NESTING + 1).rev() {
+      let json = fabric(&format!("level{}", level));
+
+      inner = jar(&[("fabric.mod.json", json.as_bytes()), ("META-INF/jars/nested.jar", &inner)]);
+    }
+
+    let forge = b"[[mods]]\nmodId = \"outer\"\nversion = \"1\"\n";
+    let mods = read(jar(&[("META-INF/mods.toml", forge), ("META-INF/jarjar/level1.jar", &inner)]));
+
+    assert_eq!(mods.len(), 1);
+    assert_eq!(mods[0].id, "outer");
+    assert_eq!(mods[0].provides, ["level1", "level2", "level3"]);
+  }
+}
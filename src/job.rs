@@ -0,0 +1,36 @@
+use std::hash::{Hash, Hasher};
+
+use iced::futures::channel::mpsc::{unbounded, UnboundedSender};
+use iced::futures::stream::BoxStream;
+use iced::futures::StreamExt;
+use iced_native::subscription::Recipe;
+
+/// Blocking work run on its own thread as a subscription, messages sent through the
+/// sender (progress, then the result) are delivered to the application
+pub struct Job<Message> {
+  id: u64,
+  work: Box<dyn FnOnce(UnboundedSender<Message>) + Send>,
+}
+
+impl<Message> Job<Message> {
+  pub fn new(id: u64, work: impl FnOnce(UnboundedSender<Message>) + Send + 'static) -> Self {
+    Self { id, work: Box::new(work) }
+  }
+}
+
+impl<H: Hasher, E, Message: Send + 'static> Recipe<H, E> for Job<Message> {
+  type Output = Message;
+
+  fn hash(&self, state: &mut H) {
+    std::any::TypeId::of::<Self>().hash(state);
+    self.id.hash(state);
+  }
+
+  fn stream(self: Box<Self>, _input: BoxStream<'static, E>) -> BoxStream<'static, Message> {
+    let (sender, receiver) = unbounded();
+
+    std::thread::spawn(move || (self.work)(sender));
+
+    receiver.boxed()
+  }
+}
@@ -0,0 +1,160 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+
+/// How long the launcher gets to fail before it counts as started
+const STARTUP_GRACE: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchOutcome {
+  /// The launcher is still running after the grace period
+  Running(u32),
+  /// The launcher exited successfully right away, which is what it does when it hands the instance over to an
+  /// already running launcher
+  HandedOver,
+}
+
+impl Display for LaunchOutcome {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LaunchOutcome::Running(pid) => write!(f, "launcher started (pid {})", pid),
+      LaunchOutcome::HandedOver => write!(f, "handed over to the running launcher"),
+    }
+  }
+}
+
+/// The binary inside a macOS app bundle, `path` itself for everything else
+fn resolve(path: &Path) -> Option<PathBuf> {
+  if path.extension().map(|it| it == "app").unwrap_or(false) {
+    return path.join("Contents/MacOS").read_dir().ok()?.flatten().map(|it| it.path()).find(|it| it.is_file());
+  }
+
+  Some(path.to_path_buf()).filter(|it| it.is_file())
+}
+
+/// Directories installers put the target launcher into
+fn install_dirs(settings: &ApplicationSettings) -> Vec<PathBuf> {
+  let mut dirs = Vec::new();
+
+  // Portable installs keep their instances next to the executable
+  if let Some(dir) = settings.mmc_dir().path.parent() {
+    dirs.push(dir.to_path_buf());
+  }
+
+  dirs.extend(settings.target.launcher().data_dirs());
+
+  #[cfg(windows)] {
+    let local = std::env::var("LOCALAPPDATA").unwrap_or_default();
+    let name = settings.target.launcher().name().replace(' ', "");
+
+    dirs.push(Path::new(&local).join("Programs").join(name));
+  }
+  #[cfg(target_os = "macos")] {
+    dirs.push(PathBuf::from("/Applications"));
+  }
+
+  dirs.extend(std::env::var_os("PATH").map(|it| std::env::split_paths(&it).collect::<Vec<_>>()).unwrap_or_default());
+  dirs.retain(|it| !it.as_os_str().is_empty());
+
+  dirs
+}
+
+/// The configured launcher executable, otherwise the first one found in a portable install, the default install
+/// locations or `PATH`
+pub fn find_executable(settings: &ApplicationSettings) -> Option<PathBuf> {
+  if let Some(path) = &settings.launcher_executable {
+    return resolve(Path::new(path));
+  }
+
+  let executables = settings.target.launcher().executables();
+
+  install_dirs(settings)
+    .into_iter()
+    .flat_map(|dir| executables.iter().map(move |it| dir.join(it)))
+    .find_map(|it| resolve(&it))
+}
+
+/// Starts the target launcher with `-l <instance>` and waits briefly to see whether it keeps running
+pub fn launch(settings: &ApplicationSettings, mp: &ModPack) -> Result<LaunchOutcome> {
+  let mmc_path = mp.mmc_path(&settings.mmc_dir())
+    .ok_or_else(|| format!("\"{}\" isn't in {}, link it first", mp, settings.target))?;
+  let id = mmc_path.file_name().unwrap_or_default();
+  let executable = find_executable(settings)
+    .ok_or_else(|| format!("No {} executable found, set launcher_executable in the settings", settings.target))?;
+
+  let mut child = Command::new(&executable)
+    .arg("-l")
+    .arg(id)
+    .spawn()
+    .map_err(|err| format!("Couldn't start {}: {}", executable.display(), err))?;
+
+  let started = Instant::now();
+
+  while started.elapsed() < STARTUP_GRACE {
+    match child.try_wait()? {
+      Some(status) if status.success() => return Ok(LaunchOutcome::HandedOver),
+      Some(status) => return Err(format!("{} exited with {}", executable.display(), status).into()),
+      None => std::thread::sleep(Duration::from_millis(100)),
+    }
+  }
+
+  Ok(LaunchOutcome::Running(child.id()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use std::os::unix::fs::PermissionsExt;
+
+  use crate::source::Source;
+
+  use super::*;
+
+  /// Settings launching `script` for instances in `dir/instances`
+  fn stub(dir: &Path, name: &str, script: &str) -> ApplicationSettings {
+    let path = dir.join(name);
+
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    ApplicationSettings {
+      mmc_directory: Some(dir.join("instances").to_string_lossy().to_string()),
+      launcher_executable: Some(path.to_string_lossy().to_string()),
+      ..ApplicationSettings::default()
+    }
+  }
+
+  // One test for every outcome, so stubs are never written while another test spawns one
+  #[test]
+  fn launch_outcomes() {
+    let dir = std::env::temp_dir().join(format!("launch-{}", std::process::id()));
+    let args = dir.join("args");
+    let mp = ModPack { dir: None, source: Source::CurseForge, name: String::from("Test Pack") };
+
+    std::fs::create_dir_all(dir.join("instances/Test Pack")).unwrap();
+
+    let settings = stub(&dir, "handover", &format!("printf '%s\\n' \"$@\" > \"{}\"", args.display()));
+    assert_eq!(launch(&settings, &mp).unwrap(), LaunchOutcome::HandedOver);
+    assert_eq!(std::fs::read_to_string(&args).unwrap(), "-l\nTest Pack\n");
+
+    let settings = stub(&dir, "fail", "exit 3");
+    assert!(launch(&settings, &mp).unwrap_err().to_string().contains("exit status: 3"));
+
+    let settings = stub(&dir, "running", "exec sleep 30");
+    match launch(&settings, &mp).unwrap() {
+      LaunchOutcome::Running(pid) => {
+        std::process::Command::new("kill").arg(pid.to_string()).status().unwrap();
+      }
+      outcome => panic!("expected the stub to keep running, got {}", outcome),
+    }
+
+    let missing = dir.join("missing").to_string_lossy().to_string();
+    assert!(launch(&ApplicationSettings { launcher_executable: Some(missing), ..settings }, &mp).is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
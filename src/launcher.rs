@@ -0,0 +1,231 @@
+use std::fmt::Formatter;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cfg::{Cfg, read_cfg};
+use crate::directories::{app_data_dirs, home};
+use crate::link::Result;
+use crate::source::SourceInstance;
+
+/// A MultiMC-like launcher instances can be linked into
+pub trait TargetLauncher {
+  fn name(&self) -> &'static str;
+
+  /// Where the launcher keeps its data when installed normally, native installs come first
+  fn data_dirs(&self) -> Vec<PathBuf>;
+
+  /// Config file inside the data directory, holds `InstanceDir` and `IconsDir`
+  fn config_file(&self) -> &'static str;
+
+  /// Executable names found in a portable install
+  fn executables(&self) -> &'static [&'static str];
+
+  /// Name of the game directory inside an instance, launchers accept both `minecraft` and `.minecraft`
+  fn game_dir_name(&self) -> &'static str {
+    ".minecraft"
+  }
+
+  /// Launcher specific `instance.cfg` keys
+  fn instance_cfg_keys(&self, _instance: &SourceInstance) -> Cfg {
+    Cfg::new()
+  }
+
+  /// Keys telling the launcher which pack an instance was installed from. Only for instances that own their files,
+  /// the launcher would otherwise update the source instance through the link
+  fn managed_pack_keys(&self, _instance: &SourceInstance) -> Cfg {
+    Cfg::new()
+  }
+
+  /// Data directory holding the config whose `InstanceDir` is `instances_dir`
+  fn data_dir(&self, instances_dir: &Path) -> PathBuf {
+    crate::detect::launcher_data_dir(self, instances_dir)
+  }
+
+  /// Icons live in `IconsDir` from the launcher config, relative to the data directory
+  fn icons_dir(&self, data_dir: &Path) -> PathBuf {
+    let cfg = read_cfg(data_dir.join(self.config_file())).unwrap_or_default();
+
+    data_dir.join(cfg.get("IconsDir").map(String::as_str).unwrap_or("icons"))
+  }
+
+  /// Adds `id` to `group` in `instgroups.json`, the format is the same for every fork
+  fn add_to_group(&self, instances_dir: &Path, group: &str, id: &str) -> Result<()> {
+    let mut groups = read_groups(instances_dir);
+
+    groups["formatVersion"] = serde_json::json!("1");
+
+    let instances = &mut groups["groups"][group]["instances"];
+
+    if !instances.is_array() {
+      *instances = serde_json::json!([]);
+    }
+
+    if let Some(instances) = instances.as_array_mut() {
+      if !instances.iter().any(|it| it == id) {
+        instances.push(serde_json::json!(id));
+      }
+    }
+
+    if groups["groups"][group]["hidden"].is_null() {
+      groups["groups"][group]["hidden"] = serde_json::json!(false);
+    }
+
+    write_groups(instances_dir, &groups)
+  }
+
+  /// Removes `id` from every group in `instgroups.json`, dropping groups that become empty
+  fn remove_from_groups(&self, instances_dir: &Path, id: &str) -> Result<()> {
+    let mut groups = read_groups(instances_dir);
+
+    if let Some(map) = groups["groups"].as_object_mut() {
+      for group in map.values_mut() {
+        if let Some(instances) = group["instances"].as_array_mut() {
+          instances.retain(|it| it != id);
+        }
+      }
+
+      map.retain(|_, group| group["instances"].as_array().map(|it| !it.is_empty()).unwrap_or(true));
+    } else {
+      return Ok(());
+    }
+
+    write_groups(instances_dir, &groups)
+  }
+}
+
+fn read_groups(instances_dir: &Path) -> serde_json::Value {
+  File::open(instances_dir.join("instgroups.json"))
+    .ok()
+    .and_then(|it| serde_json::from_reader(it).ok())
+    .filter(serde_json::Value::is_object)
+    .unwrap_or_else(|| serde_json::json!({ "formatVersion": "1", "groups": {} }))
+}
+
+fn write_groups(instances_dir: &Path, groups: &serde_json::Value) -> Result<()> {
+  std::fs::write(instances_dir.join("instgroups.json"), serde_json::to_string_pretty(groups)?)?;
+
+  Ok(())
+}
+
+/// `ManagedPack*` keys understood by PolyMC and Prism, only written for copies of instances installed from a pack
+fn managed_pack_keys(instance: &SourceInstance) -> Cfg {
+  let mut cfg = Cfg::new();
+
+  if let Some(pack) = &instance.managed {
+    cfg.insert(String::from("ManagedPack"), String::from("true"));
+    cfg.insert(String::from("ManagedPackType"), pack.kind.to_string());
+    cfg.insert(String::from("ManagedPackID"), pack.id.clone());
+    cfg.insert(String::from("ManagedPackName"), pack.name.clone());
+    cfg.insert(String::from("ManagedPackVersionID"), pack.version_id.clone());
+    cfg.insert(String::from("ManagedPackVersionName"), pack.version_name.clone());
+  }
+
+  cfg
+}
+
+pub struct MultiMC;
+pub struct PolyMC;
+pub struct Prism;
+
+impl TargetLauncher for MultiMC {
+  fn name(&self) -> &'static str { "MultiMC" }
+
+  fn data_dirs(&self) -> Vec<PathBuf> {
+    let mut dirs = app_data_dirs("MultiMC", None);
+
+    #[cfg(target_os = "macos")] dirs.push(PathBuf::from("/Applications/MultiMC.app/Data"));
+    #[cfg(target_os = "linux")] dirs.push(home().join(".multimc"));
+
+    dirs.push(home().join("MultiMC"));
+    dirs
+  }
+
+  fn config_file(&self) -> &'static str { "multimc.cfg" }
+
+  fn executables(&self) -> &'static [&'static str] {
+    &["MultiMC.exe", "MultiMC", "MultiMC.app"]
+  }
+
+  fn game_dir_name(&self) -> &'static str {
+    "minecraft"
+  }
+}
+
+impl TargetLauncher for PolyMC {
+  fn name(&self) -> &'static str { "PolyMC" }
+
+  fn data_dirs(&self) -> Vec<PathBuf> {
+    app_data_dirs("PolyMC", Some("org.polymc.PolyMC"))
+  }
+
+  fn config_file(&self) -> &'static str { "polymc.cfg" }
+
+  fn executables(&self) -> &'static [&'static str] {
+    &["polymc.exe", "polymc", "PolyMC.app"]
+  }
+
+  fn managed_pack_keys(&self, instance: &SourceInstance) -> Cfg {
+    managed_pack_keys(instance)
+  }
+}
+
+impl TargetLauncher for Prism {
+  fn name(&self) -> &'static str { "Prism Launcher" }
+
+  fn data_dirs(&self) -> Vec<PathBuf> {
+    app_data_dirs("PrismLauncher", Some("org.prismlauncher.PrismLauncher"))
+  }
+
+  fn config_file(&self) -> &'static str { "prismlauncher.cfg" }
+
+  fn executables(&self) -> &'static [&'static str] {
+    &["prismlauncher.exe", "prismlauncher", "Prism Launcher.app"]
+  }
+
+  fn instance_cfg_keys(&self, _instance: &SourceInstance) -> Cfg {
+    Cfg::from([(String::from("ConfigVersion"), String::from("1.2"))])
+  }
+
+  fn managed_pack_keys(&self, instance: &SourceInstance) -> Cfg {
+    managed_pack_keys(instance)
+  }
+}
+
+/// Selectable target launcher, stored in the settings
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+  #[default] MultiMC,
+  PolyMC,
+  Prism,
+}
+
+impl Target {
+  pub const ALL: [Target; 3] = [Target::MultiMC, Target::PolyMC, Target::Prism];
+
+  pub fn launcher(&self) -> &'static dyn TargetLauncher {
+    match self {
+      Target::MultiMC => &MultiMC,
+      Target::PolyMC => &PolyMC,
+      Target::Prism => &Prism,
+    }
+  }
+}
+
+impl std::fmt::Display for Target {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.launcher().name())
+  }
+}
+
+/// Game directory of an instance, whichever of `.minecraft` and `minecraft` exists
+pub fn game_dir(instance_path: &Path) -> PathBuf {
+  let dot = instance_path.join(".minecraft");
+
+  if dot.symlink_metadata().is_ok() {
+    dot
+  } else {
+    instance_path.join("minecraft")
+  }
+}
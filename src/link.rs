@@ -1,16 +1,102 @@
 use std::fmt::Formatter;
-use std::fs::{create_dir, File, remove_dir_all};
+use std::fs::{copy, create_dir, create_dir_all, File, remove_dir_all, remove_file};
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::directories::{CurseForgeDirectory, Directory, MultiMCDirectory};
+use crate::cfg::{format_cfg, read_cfg};
+use crate::directories::{check_name, CurseForgeDirectory, Directory, MultiMCDirectory};
+use crate::launcher::{game_dir, TargetLauncher};
+use crate::marker::{is_marked, is_symlink_to, Marker, MarkerKind};
+use crate::misc::ApplicationSettings;
 use crate::modpack::ModPack;
+use crate::source::{ModLoader, SourceInstance};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CFMinecraftInstance {
   pub name: String,
   #[serde(alias = "baseModLoader")] pub loader: CFBaseModLoader,
+  #[serde(alias = "installPath", default)] pub install_path: Option<String>,
+  #[serde(alias = "profileImagePath", default)] pub profile_image_path: Option<String>,
+  #[serde(alias = "projectID", default)] pub project_id: u64,
+  #[serde(alias = "fileID", default)] pub file_id: u64,
+  #[serde(default)] pub manifest: Option<CFManifest>,
+  #[serde(alias = "installedAddons", default)] pub installed_addons: Vec<CFInstalledAddon>,
+}
+
+/// The modpack manifest CurseForge keeps for instances installed from a pack
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CFManifest {
+  #[serde(default)] pub name: String,
+  #[serde(default)] pub version: String,
+}
+
+/// A mod, resource pack or shader CurseForge installed into the instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CFInstalledAddon {
+  #[serde(alias = "addonID")] pub addon_id: u64,
+  #[serde(default)] pub name: String,
+  #[serde(alias = "installedFile", default)] pub installed_file: Option<CFInstalledFile>,
+  /// Older instances record the folder the addon goes into here
+  #[serde(alias = "categorySection", default)] pub category_section: Option<CFCategorySection>,
+  /// Project class, newer instances only have this
+  #[serde(alias = "classId", alias = "categoryClassId", default)] pub class_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CFCategorySection {
+  #[serde(default)] pub path: String,
+}
+
+impl CFInstalledAddon {
+  /// Folder below the game directory the addon is installed into, `None` when the instance doesn't say
+  pub fn dir(&self) -> Option<String> {
+    let path = self.category_section.as_ref()
+      .map(|it| it.path.trim_matches(['/', '\\']).to_string())
+      .filter(|it| !it.is_empty());
+
+    path.or_else(|| match self.class_id? {
+      6 => Some(String::from("mods")),
+      12 => Some(String::from("resourcepacks")),
+      6552 => Some(String::from("shaderpacks")),
+      _ => None,
+    })
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CFInstalledFile {
+  pub id: u64,
+  #[serde(alias = "fileName")] pub file_name: String,
+  #[serde(alias = "displayName", default)] pub display_name: String,
+  /// 1 for release, 2 for beta, 3 for alpha
+  #[serde(alias = "releaseType", default)] pub release_type: u8,
+  #[serde(alias = "downloadUrl", default)] pub download_url: Option<String>,
+  #[serde(default)] pub dependencies: Vec<CFFileDependency>,
+  /// See [`crate::hash::cf_fingerprint`]
+  #[serde(alias = "packageFingerprint", default)] pub fingerprint: u32,
+  #[serde(alias = "fileLength", default)] pub file_length: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CFFileDependency {
+  #[serde(alias = "addonId")] pub addon_id: u64,
+  /// 1 embedded, 2 optional, 3 required, 4 tool, 5 incompatible, 6 include
+  #[serde(rename = "type", default)] pub kind: u8,
+}
+
+impl CFMinecraftInstance {
+  /// `installPath` as a host path, Windows paths are translated through the
+  /// Wine prefix `instance_dir` lives in when not running on Windows
+  pub fn host_install_path(&self, instance_dir: &Path) -> Option<PathBuf> {
+    crate::wine::host_path(self.install_path.as_deref()?, instance_dir)
+  }
+
+  /// Custom profile image set in CurseForge, as a host path
+  pub fn host_profile_image_path(&self, instance_dir: &Path) -> Option<PathBuf> {
+    crate::wine::host_path(self.profile_image_path.as_deref()?, instance_dir)
+  }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,28 +106,23 @@ pub struct CFBaseModLoader {
   #[serde(alias = "minecraftVersion")] pub mc_version: String,
 }
 
-pub enum CFMinecraftLoaderVersion {
-  Forge(String),
-  Fabric(String),
-  Unknown,
-}
-
 impl CFBaseModLoader {
-  pub fn version(&self) -> CFMinecraftLoaderVersion {
-    match self.name.split_once("-") {
-      Some(("forge", _)) => CFMinecraftLoaderVersion::Forge(self.version.clone()),
-      Some(("fabric", _)) => CFMinecraftLoaderVersion::Fabric(self.version.clone()),
-      _ => CFMinecraftLoaderVersion::Unknown
+  pub fn version(&self) -> ModLoader {
+    match self.name.split_once('-') {
+      Some((name, _)) => ModLoader::from_name(name, self.version.clone()),
+      _ => ModLoader::Vanilla
     }
   }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)] // kept for code reading CurseForge's mod loader lists
 pub struct CFModLoadersJson {
   pub id: String,
 }
 
 #[derive(Debug)]
+#[allow(dead_code)] // context is only read through Debug
 pub struct LinkError {
   pub msg: &'static str,
   pub mmc: MultiMCDirectory,
@@ -51,12 +132,12 @@ pub struct LinkError {
 
 impl std::error::Error for LinkError {}
 
-fn err<'a>(
+fn err<T>(
   msg: &'static str,
   mmc: MultiMCDirectory,
   cf: CurseForgeDirectory,
   selected: ModPack,
-) -> Result<()> {
+) -> Result<T> {
   Err(Box::new(LinkError { msg, mmc, cf, selected }))
 }
 
@@ -68,7 +149,17 @@ impl std::fmt::Display for LinkError {
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-pub fn gen_mmc_instance_cfg(instance: &CFMinecraftInstance) -> String {
+pub fn get_cf_instance(mp: &ModPack) -> Result<CFMinecraftInstance> {
+  let path = mp.path().join("minecraftinstance.json");
+
+  Ok(serde_json::from_reader(&File::open(&path)?)?)
+}
+
+pub fn gen_mmc_instance_cfg(
+  launcher: &dyn TargetLauncher,
+  instance: &SourceInstance,
+  icon_key: &str,
+) -> String {
   let mut str = String::new();
 
   str.push_str("InstanceType=OneSix\n");
@@ -81,97 +172,251 @@ pub fn gen_mmc_instance_cfg(instance: &CFMinecraftInstance) -> String {
   str.push_str("OverrideMemory=false\n");
   str.push_str("OverrideNativeWorkarounds=false\n");
   str.push_str("OverrideWindow=false\n");
-  str.push_str("iconKey=default\n");
+  str.push_str(format!("iconKey={}\n", icon_key).as_str());
   str.push_str(format!("name={}\n", instance.name).as_str());
   str.push_str("notes=\n");
+  str.push_str(format_cfg(&launcher.instance_cfg_keys(instance)).as_str());
 
   str
 }
 
-pub fn gen_mmc_pack_json(instance: &CFMinecraftInstance) -> serde_json::Value {
-  let minecraft_component = |instance: &CFMinecraftInstance| {
+/// Adds the launcher's `ManagedPack*` keys to the `instance.cfg` at `mmc_path`, for instances that own their files
+pub fn write_managed_pack_keys(launcher: &dyn TargetLauncher, mmc_path: &Path, instance: &SourceInstance) -> Result<()> {
+  let keys = launcher.managed_pack_keys(instance);
+
+  if keys.is_empty() {
+    return Ok(());
+  }
+
+  let path = mmc_path.join("instance.cfg");
+  let mut cfg = read_cfg(&path)?;
+
+  cfg.extend(keys);
+  std::fs::write(path, format_cfg(&cfg))?;
+
+  Ok(())
+}
+
+pub fn gen_mmc_pack_json(instance: &SourceInstance) -> serde_json::Value {
+  let minecraft_component = |instance: &SourceInstance| {
     serde_json::json!({
       "cachedName": "Minecraft",
       "cachedRequires": [],
-      "cachedVersion": instance.loader.mc_version,
+      "cachedVersion": instance.mc_version,
       "important": true,
       "uid": "net.minecraft",
-      "version": instance.loader.mc_version
+      "version": instance.mc_version
     })
   };
 
-  let version_component = |instance: &CFMinecraftInstance| {
-    match instance.loader.version() {
-      CFMinecraftLoaderVersion::Fabric(version) => serde_json::json!({
-        "cachedName": "Fabric Loader",
-        "uid": "net.fabricmc.fabric-loader",
-        "version": version
-      }),
-      CFMinecraftLoaderVersion::Forge(version) => serde_json::json!({
-        "cachedName": "Forge",
-        "uid": "net.minecraftforge",
-        "version": version
-      }),
-      _ => serde_json::json!({})
-    }
+  let version_component = |instance: &SourceInstance| {
+    let (name, uid) = instance.loader.component()?;
+
+    Some(serde_json::json!({
+      "cachedName": name,
+      "uid": uid,
+      "version": instance.loader.version()
+    }))
   };
 
+  let components = std::iter::once(minecraft_component(instance))
+    .chain(version_component(instance))
+    .collect::<Vec<_>>();
+
   serde_json::json!(
     {
-      "components": [
-        minecraft_component(instance),
-        version_component(instance)
-      ],
+      "components": components,
       "formatVersion": 1
     }
   )
 }
 
-pub fn get_cf_instance(mp: &ModPack) -> Result<CFMinecraftInstance> {
-  let path = mp.cf_path().join("minecraftinstance.json");
+/// Copies the instance icon into the launcher's icons, returns the icon key to use
+fn import_icon(
+  launcher: &dyn TargetLauncher,
+  mmc: &MultiMCDirectory,
+  instance: &SourceInstance,
+) -> Option<String> {
+  let image = instance.icon.as_ref().filter(|it| it.is_file())?;
+  let extension = image.extension()?.to_str()?;
+  let icons_dir = launcher.icons_dir(&launcher.data_dir(mmc.path()));
+  let key = icon_key(&instance.name);
 
-  Ok(serde_json::from_reader(&File::open(&path)?)?)
+  create_dir_all(&icons_dir).ok()?;
+  copy(image, icons_dir.join(format!("{}.{}", key, extension))).ok()?;
+
+  Some(key)
+}
+
+fn remove_icon(launcher: &dyn TargetLauncher, mmc: &MultiMCDirectory, instance: &SourceInstance) {
+  let key = icon_key(&instance.name);
+  let icons = launcher.icons_dir(&launcher.data_dir(mmc.path())).read_dir().ok();
+
+  for icon in icons.into_iter().flatten().flatten() {
+    if icon.path().file_stem().map(|it| it == key.as_str()).unwrap_or(false) {
+      let _ = remove_file(icon.path());
+    }
+  }
+}
+
+fn icon_key(name: &str) -> String {
+  let key = name
+    .chars()
+    .map(|it| if it.is_ascii_alphanumeric() { it.to_ascii_lowercase() } else { '_' })
+    .collect::<String>();
+
+  format!("c2m_{}", key)
 }
 
 pub fn unlink(
+  settings: &ApplicationSettings,
   mmc: MultiMCDirectory,
   mp: ModPack,
 ) -> Result<()> {
-  let instance = get_cf_instance(&mp)?;
+  let launcher = settings.target.launcher();
+  let instance = mp.instance()?;
+
+  check_name(&instance.name)?;
+
   let mmc_path = mmc.path.join(&instance.name);
+  let mmc_game_dir = game_dir(&mmc_path);
+  let linked = is_symlink_to(&mmc_game_dir, &instance.game_dir);
+  let owned = linked
+    || is_marked(&mmc_path, MarkerKind::Link)
+    || is_marked(&mmc_path, MarkerKind::Detached);
+
+  if !owned {
+    return Err(Box::from("This instance wasn't linked by this application"));
+  }
+
+  // Saves behind the symlink stay with the source instance, anything else is about to be deleted
+  if !linked {
+    crate::backup::backup_saves(settings, &instance.name, &mmc_game_dir, "unlink")?;
+  }
 
+  crate::shared::unshare(settings, &instance.game_dir)?;
   remove_dir_all(mmc_path)?;
+  launcher.remove_from_groups(mmc.path(), &instance.name)?;
+
+  remove_icon(launcher, &mmc, &instance);
+  crate::shortcuts::remove(&instance.name)?;
 
   Ok(())
 }
 
+/// Links `mp` into the target launcher, returns warnings for the optional steps that failed. Once the symlink
+/// and marker are in place the instance is usable, so sharing folders, merging options and servers, the
+/// pre-launch check, groups and desktop entries only warn instead of failing the link
 pub fn link(
+  settings: &ApplicationSettings,
   mmc: MultiMCDirectory,
   cf: CurseForgeDirectory,
   mp: ModPack,
-) -> Result<()> {
-  let instance = get_cf_instance(&mp)?;
-  let mmc_pack = serde_json::to_string_pretty(&gen_mmc_pack_json(&instance))?;
-  let mmc_cfg = gen_mmc_instance_cfg(&instance);
+) -> Result<Vec<String>> {
+  let launcher = settings.target.launcher();
+  let instance = mp.instance()?;
+
+  check_name(&instance.name)?;
+
   let mmc_path = mmc.path().join(&instance.name);
 
   if mmc_path.exists() {
     return err("A folder with that name already exists", mmc, cf, mp);
   }
 
+  let icon_key = import_icon(launcher, &mmc, &instance).unwrap_or_else(|| String::from("default"));
+  let mmc_pack = serde_json::to_string_pretty(&gen_mmc_pack_json(&instance))?;
+  let mmc_cfg = gen_mmc_instance_cfg(launcher, &instance, &icon_key);
+
   create_dir(&mmc_path)?;
 
   let mut mmc_cfg_file = File::create(mmc_path.join("instance.cfg"))?;
   let mut mmc_pack_file = File::create(mmc_path.join("mmc-pack.json"))?;
 
-  mmc_cfg_file.write(mmc_cfg.as_bytes())?;
-  mmc_pack_file.write(mmc_pack.as_bytes())?;
+  mmc_cfg_file.write_all(mmc_cfg.as_bytes())?;
+  mmc_pack_file.write_all(mmc_pack.as_bytes())?;
 
-  match symlink::symlink_dir(mp.cf_path(), mmc_path.join("minecraft")) {
-    Ok(_) => Ok(()),
+  match symlink::symlink_dir(&instance.game_dir, mmc_path.join(launcher.game_dir_name())) {
+    Ok(_) => {}
     Err(_) => {
       remove_dir_all(&mmc_path)?;
-      err("No permission to create symlink (Needs admin perms)", mmc, cf, mp)
+      return err("No permission to create symlink (Needs admin perms)", mmc, cf, mp);
     }
   }
-}
\ No newline at end of file
+
+  // Without the marker the instance can't be told apart from a legacy link, so it isn't left behind half done
+  if let Err(error) = Marker::new(MarkerKind::Link, &instance.game_dir).write(&mmc_path) {
+    remove_dir_all(&mmc_path)?;
+    return Err(error);
+  }
+
+  let mut warnings = Vec::new();
+  let mut attempt = |what: &str, result: Result<()>| {
+    if let Err(error) = result {
+      warnings.push(format!("Couldn't {}: {}", what, error));
+    }
+  };
+
+  attempt("share folders", crate::shared::share(settings, &instance.name, &instance.game_dir).map(drop));
+  attempt("merge options", crate::options::apply(settings, &instance.game_dir, &instance.mc_version, false).map(drop));
+  attempt("merge servers", crate::servers::apply(settings, &instance.game_dir).map(drop));
+
+  if settings.check_before_launch {
+    attempt("install the pre-launch check", crate::check::install_hook(launcher, &mmc_path));
+  }
+
+  if let Some(group) = settings.group.as_deref().filter(|it| !it.is_empty()) {
+    attempt("add it to the group", launcher.add_to_group(mmc.path(), group, &instance.name));
+  }
+
+  if settings.desktop_shortcuts {
+    attempt("create the desktop entry", crate::shortcuts::create(settings, &instance, &mmc_path).map(drop));
+  }
+
+  Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::launcher::Target;
+  use crate::source::ManagedPack;
+
+  use super::*;
+
+  #[test]
+  fn managed_pack_keys_are_only_added_to_copies() {
+    let dir = std::env::temp_dir().join(format!("link-{}", std::process::id()));
+    let instance = SourceInstance {
+      name: String::from("Pack"),
+      mc_version: String::from("1.20.1"),
+      loader: ModLoader::Fabric(String::from("0.14.21")),
+      game_dir: dir.join(".minecraft"),
+      icon: None,
+      managed: Some(ManagedPack {
+        kind: "modrinth",
+        id: String::from("abc"),
+        name: String::from("Pack"),
+        version_id: String::from("v1"),
+        version_name: String::from("1.0"),
+      }),
+    };
+
+    for target in Target::ALL {
+      let launcher = target.launcher();
+      let cfg = gen_mmc_instance_cfg(launcher, &instance, "default");
+
+      assert!(!cfg.contains("ManagedPack"), "{:?} writes pack keys into linked instances", target);
+
+      std::fs::create_dir_all(&dir).unwrap();
+      std::fs::write(dir.join("instance.cfg"), cfg).unwrap();
+      write_managed_pack_keys(launcher, &dir, &instance).unwrap();
+
+      let cfg = read_cfg(dir.join("instance.cfg")).unwrap();
+
+      assert_eq!(cfg.contains_key("ManagedPackID"), target != Target::MultiMC);
+      assert_eq!(cfg.get("name").map(String::as_str), Some("Pack"));
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,320 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::export::{write_zip, Entry};
+use crate::inventory::inventory;
+use crate::launcher::game_dir;
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+
+/// Errors from `latest.log` kept in the summary, the last ones are usually the ones that matter
+const MAX_ERRORS: usize = 20;
+
+/// Lines naming the loader version in crash reports, in order of preference
+const LOADER_PREFIXES: [&str; 6] = ["NeoForge:", "Forge:", "FML:", "fabricloader:", "quilt_loader:", "Fabric Loader:"];
+
+/// The newest crash report and log of an instance, along with the lines that explain the crash
+#[derive(Debug, Clone, Default)]
+pub struct CrashLogs {
+  pub name: String,
+  pub mc_version: String,
+  pub loader: String,
+  pub crash_report: Option<PathBuf>,
+  pub latest_log: Option<PathBuf>,
+  /// What the crash report says the game was doing
+  pub description: Option<String>,
+  pub exception: Option<String>,
+  pub suspected_mods: Vec<String>,
+  /// Loader version as the crash report or log puts it
+  pub loader_version: Option<String>,
+  pub errors: Vec<String>,
+  /// Mods of the instance, one per line
+  pub mods: Vec<String>,
+}
+
+fn read_lossy(path: &Path) -> Option<String> {
+  std::fs::read(path).ok().map(|it| String::from_utf8_lossy(&it).to_string())
+}
+
+fn newest_crash_report(game_dir: &Path) -> Option<PathBuf> {
+  game_dir.join("crash-reports")
+    .read_dir()
+    .ok()?
+    .flatten()
+    .filter(|it| it.path().extension().map(|it| it == "txt").unwrap_or(false))
+    .filter_map(|it| Some((it.metadata().ok()?.modified().ok()?, it.path())))
+    .max()
+    .map(|(_, path)| path)
+}
+
+impl CrashLogs {
+  /// Collects the logs of `mp` from the game directory the target launcher runs it in
+  pub fn collect(settings: &ApplicationSettings, mp: &ModPack) -> Result<CrashLogs> {
+    let instance = mp.instance()?;
+    let dir = mp.mmc_path(&settings.mmc_dir())
+      .map(|it| game_dir(&it))
+      .filter(|it| it.is_dir())
+      .unwrap_or_else(|| instance.game_dir.clone());
+
+    let mut logs = CrashLogs {
+      name: instance.name.clone(),
+      mc_version: instance.mc_version.clone(),
+      loader: instance.loader.id().unwrap_or_else(|| String::from("vanilla")),
+      crash_report: newest_crash_report(&dir),
+      latest_log: Some(dir.join("logs").join("latest.log")).filter(|it| it.is_file()),
+      mods: inventory(mp).unwrap_or_default().iter().map(ToString::to_string).collect(),
+      ..CrashLogs::default()
+    };
+
+    if let Some(text) = logs.crash_report.as_deref().and_then(read_lossy) {
+      logs.read_crash_report(&text);
+    }
+
+    if let Some(text) = logs.latest_log.as_deref().and_then(read_lossy) {
+      logs.read_log(&text);
+    }
+
+    Ok(logs)
+  }
+
+  fn read_crash_report(&mut self, text: &str) {
+    let lines = text.lines().collect::<Vec<_>>();
+
+    for (index, line) in lines.iter().enumerate() {
+      if let Some(description) = line.strip_prefix("Description: ") {
+        self.description = Some(description.trim().to_string());
+        self.exception = lines[index + 1..].iter()
+          .map(|it| it.trim())
+          .find(|it| !it.is_empty())
+          .map(str::to_string);
+      }
+
+      // Forge lists the mods it suspects below this, one per line indented by a single tab
+      if line.starts_with("Suspected Mod") {
+        let value = line.split_once(':').map(|it| it.1.trim()).unwrap_or_default();
+
+        if !value.is_empty() && !value.eq_ignore_ascii_case("none") {
+          self.suspected_mods.push(value.to_string());
+        }
+
+        self.suspected_mods.extend(
+          lines[index + 1..].iter()
+            .take_while(|it| it.starts_with('\t'))
+            .filter(|it| !it.starts_with("\t\t"))
+            .map(|it| it.trim().to_string())
+        );
+      }
+
+      if let Some(name) = line.strip_prefix("-- MOD ").and_then(|it| it.strip_suffix(" --")) {
+        self.suspected_mods.push(name.to_string());
+      }
+    }
+
+    self.loader_version = LOADER_PREFIXES.iter()
+      .find_map(|prefix| lines.iter().map(|it| it.trim()).find(|it| it.starts_with(prefix)))
+      .map(str::to_string);
+  }
+
+  fn read_log(&mut self, text: &str) {
+    let mut errors = Vec::new();
+
+    for line in text.lines() {
+      if line.contains("/ERROR]") || line.contains("/FATAL]") || line.starts_with("Caused by:") {
+        errors.push(line.trim().to_string());
+      }
+
+      if self.loader_version.is_none() {
+        // Fabric and Quilt log `Loading Minecraft 1.20.1 with Fabric Loader 0.14.21`, Forge passes its
+        // version to ModLauncher as `--fml.forgeVersion, 47.1.0`
+        self.loader_version = line.split_once(" with ")
+          .filter(|_| line.contains("Loading Minecraft"))
+          .map(|it| it.1.trim().to_string())
+          .or_else(|| {
+            let version = line.split_once("--fml.forgeVersion, ")?.1.split([',', ']']).next()?;
+            Some(format!("Forge {}", version.trim()))
+          });
+      }
+    }
+
+    errors.dedup();
+    self.errors = errors.split_off(errors.len().saturating_sub(MAX_ERRORS));
+  }
+
+  /// Zips the summary along with the crash report and log themselves
+  pub fn write_zip(&self, output: &Path) -> Result<()> {
+    let mut entries = vec![Entry::Generated(String::from("summary.txt"), self.to_string().into_bytes())];
+
+    if let Some(path) = &self.crash_report {
+      let name = path.file_name().unwrap_or_default().to_string_lossy();
+      entries.push(Entry::File(format!("crash-reports/{}", name), path.clone()));
+    }
+
+    if let Some(path) = &self.latest_log {
+      entries.push(Entry::File(String::from("logs/latest.log"), path.clone()));
+    }
+
+    write_zip(output, entries)
+  }
+}
+
+impl Display for CrashLogs {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "{}, Minecraft {}, {}", self.name, self.mc_version, self.loader)?;
+
+    if let Some(version) = &self.loader_version {
+      writeln!(f, "Loader: {}", version)?;
+    }
+
+    match &self.crash_report {
+      Some(path) => writeln!(f, "Crash report: {}", path.file_name().unwrap_or_default().to_string_lossy())?,
+      None => writeln!(f, "No crash report")?,
+    }
+
+    if let Some(description) = &self.description {
+      writeln!(f, "Description: {}", description)?;
+    }
+
+    if let Some(exception) = &self.exception {
+      writeln!(f, "Exception: {}", exception)?;
+    }
+
+    if !self.suspected_mods.is_empty() {
+      writeln!(f, "Suspected mods:")?;
+
+      for name in &self.suspected_mods {
+        writeln!(f, "  {}", name)?;
+      }
+    }
+
+    if !self.errors.is_empty() {
+      writeln!(f, "Errors in latest.log:")?;
+
+      for error in &self.errors {
+        writeln!(f, "  {}", error)?;
+      }
+    }
+
+    writeln!(f, "Mods ({}):", self.mods.len())?;
+
+    for line in &self.mods {
+      writeln!(f, "  {}", line)?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const FORGE_CRASH: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2023-08-01 12:00:00
+Description: Ticking entity
+
+java.lang.NullPointerException: Cannot invoke \"net.minecraft.world.entity.Entity.m_9236_()\"
+\tat com.example.Foo.tick(Foo.java:10) ~[example.jar%23100!/:1.0] {re:classloading}
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- Head --
+Thread: Server thread
+Suspected Mods: 
+\tExample Mod (example), Version: 1.0
+\t\tIssue tracker URL: https://example.com/issues
+\tCreate (create), Version: 0.5.1.f
+Stacktrace:
+\tat com.example.Foo.tick(Foo.java:10) ~[example.jar%23100!/:1.0] {re:classloading}
+
+-- MOD create --
+Details:
+\tMod File: /home/user/.minecraft/mods/create.jar
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.1
+\tFML: 47.1
+\tForge: net.minecraftforge:47.1.0
+";
+
+  #[test]
+  fn forge_crash_report() {
+    let mut logs = CrashLogs::default();
+
+    logs.read_crash_report(FORGE_CRASH);
+
+    assert_eq!(logs.description.as_deref(), Some("Ticking entity"));
+    assert_eq!(
+      logs.exception.as_deref(),
+      Some("java.lang.NullPointerException: Cannot invoke \"net.minecraft.world.entity.Entity.m_9236_()\""),
+    );
+    assert_eq!(logs.suspected_mods, [
+      "Example Mod (example), Version: 1.0",
+      "Create (create), Version: 0.5.1.f",
+      "create",
+    ]);
+    assert_eq!(logs.loader_version.as_deref(), Some("Forge: net.minecraftforge:47.1.0"));
+  }
+
+  #[test]
+  fn single_suspected_mod_and_none() {
+    let mut logs = CrashLogs::default();
+
+    logs.read_crash_report("Suspected Mod: Sodium (sodium)\nSuspected Mods: NONE\n-- MOD jei --\n");
+
+    assert_eq!(logs.suspected_mods, ["Sodium (sodium)", "jei"]);
+    assert_eq!(logs.loader_version, None);
+  }
+
+  #[test]
+  fn loader_version_from_the_log() {
+    let fabric = "[12:00:00] [main/INFO]: Loading Minecraft 1.20.1 with Fabric Loader 0.14.21\n";
+    let forge = concat!(
+      "[12:00:00] [main/INFO] [cpw.mods.modlauncher.Launcher/MODLAUNCHER]: ModLauncher running: args [--username, Dev, ",
+      "--version, 1.20.1, --gameDir, ., --fml.forgeVersion, 47.1.0, --fml.mcVersion, 1.20.1]\n",
+    );
+    let last = "[12:00:00] [main/INFO]: args [--fml.forgeVersion, 47.2.0]\n";
+
+    let mut logs = CrashLogs::default();
+    logs.read_log(fabric);
+    assert_eq!(logs.loader_version.as_deref(), Some("Fabric Loader 0.14.21"));
+
+    let mut logs = CrashLogs::default();
+    logs.read_log(forge);
+    assert_eq!(logs.loader_version.as_deref(), Some("Forge 47.1.0"));
+
+    let mut logs = CrashLogs::default();
+    logs.read_log(last);
+    assert_eq!(logs.loader_version.as_deref(), Some("Forge 47.2.0"));
+
+    // The crash report's version wins
+    let mut logs = CrashLogs::default();
+    logs.read_crash_report(FORGE_CRASH);
+    logs.read_log(fabric);
+    assert_eq!(logs.loader_version.as_deref(), Some("Forge: net.minecraftforge:47.1.0"));
+  }
+
+  #[test]
+  fn errors_are_deduplicated_and_capped() {
+    let mut log = String::from("[12:00:00] [main/INFO]: Fine\n");
+
+    for index in 0..MAX_ERRORS + 5 {
+      log.push_str(&format!("[12:00:00] [Render thread/ERROR]: Error {}\n", index));
+      log.push_str(&format!("[12:00:00] [Render thread/ERROR]: Error {}\n", index));
+    }
+
+    log.push_str("[12:00:01] [main/FATAL]: Crashed\nCaused by: java.lang.RuntimeException\n");
+
+    let mut logs = CrashLogs::default();
+
+    logs.read_log(&log);
+
+    assert_eq!(logs.errors.len(), MAX_ERRORS);
+    assert_eq!(logs.errors[0], "[12:00:00] [Render thread/ERROR]: Error 7");
+    assert_eq!(logs.errors[MAX_ERRORS - 2..], ["[12:00:01] [main/FATAL]: Crashed", "Caused by: java.lang.RuntimeException"]);
+  }
+}
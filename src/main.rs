@@ -3,22 +3,64 @@
 use iced::*;
 use iced_native::{Element, Event};
 
+use crate::detect::Candidate;
+use crate::detach::{Progress, Stage};
 use crate::directories::{CurseForgeDirectory, Directory, MultiMCDirectory};
 use crate::ext::ButtonExt;
+use crate::inventory::ModEntry;
+use crate::job::Job;
+use crate::launcher::Target;
+use crate::logs::CrashLogs;
 use crate::misc::{ApplicationSettings, Flags, hide_console, icon};
 use crate::modpack::ModPack;
+use crate::reverse::MMCInstance;
+use crate::source::Source;
 
+mod backup;
+mod cfg;
+mod check;
+mod cfpack;
+mod cli;
+mod dedupe;
+mod detach;
+mod detect;
+mod diff;
 mod directories;
+mod export;
 mod ext;
+mod hash;
+mod import;
+mod inventory;
+mod jar;
+mod job;
+mod launch;
+mod launcher;
 mod link;
+mod logs;
+mod marker;
 mod misc;
 mod modpack;
+mod options;
+mod mrpack;
+mod nbt;
+mod report;
+mod reverse;
+mod servers;
+mod shared;
+mod shortcuts;
+mod snapshot;
+mod source;
+mod state;
+mod verify;
+mod version;
+mod wine;
 
-const NAME: &'static str = env!("CARGO_PKG_NAME");
-const TITLE: &'static str = "CurseForge to MultiMC";
-const GITHUB_URL: &'static str = env!("CARGO_PKG_REPOSITORY");
+const NAME: &str = env!("CARGO_PKG_NAME");
+const TITLE: &str = "CurseForge to MultiMC";
+const GITHUB_URL: &str = env!("CARGO_PKG_REPOSITORY");
 const ERR_COLOR: Color = Color { r: 0.8, g: 0.0, b: 0.0, a: 1.0 };
 const OK_COLOR: Color = Color { r: 0.0, g: 0.8, b: 0.0, a: 1.0 };
+const WARN_COLOR: Color = Color { r: 0.9, g: 0.5, b: 0.0, a: 1.0 };
 const IMPORTANT_SIZE: u16 = 24;
 
 macro_rules! set_info_if_err {
@@ -30,6 +72,10 @@ macro_rules! set_info_if_err {
 fn main() -> Result {
   let flags = Flags::new();
 
+  if let Some(code) = crate::cli::run(&flags) {
+    std::process::exit(code);
+  }
+
   hide_console();
 
   <CurseForgeToMultiMC as Application>::run(Settings {
@@ -45,6 +91,49 @@ fn main() -> Result {
   })
 }
 
+/// Detected directories shown below a directory row after pressing "Browse"
+#[derive(Default)]
+struct CandidateList {
+  items: Vec<(Candidate, button::State)>,
+  other_state: button::State,
+  shown: bool,
+}
+
+impl CandidateList {
+  fn show(&mut self, candidates: Vec<Candidate>) {
+    self.items = candidates.into_iter().map(|it| (it, button::State::new())).collect();
+    self.shown = true;
+  }
+
+  fn pick(&mut self, index: usize) -> Option<Candidate> {
+    self.shown = false;
+    self.items.get(index).map(|(it, _)| it.clone())
+  }
+
+  fn view(&mut self, on_pick: fn(usize) -> Message, other: Message) -> Element<'_, Message, Renderer> {
+    if !self.shown {
+      return Space::with_height(Length::Units(0)).into();
+    }
+
+    let column = self.items.iter_mut()
+      .enumerate()
+      .fold(Column::new().spacing(4), |column, (index, (candidate, state))| {
+        column.push(
+          Button::new(state, Text::new(candidate.to_string()))
+            .width(Length::Fill)
+            .on_press(on_pick(index))
+        )
+      });
+
+    column
+      .push(
+        Button::new(&mut self.other_state, Text::new("Choose another folder..."))
+          .on_press(other)
+      )
+      .into()
+  }
+}
+
 #[derive(Default)]
 struct CurseForgeToMultiMC {
   mmc_d: MultiMCDirectory,
@@ -55,13 +144,39 @@ struct CurseForgeToMultiMC {
   cf_ti_d_state: text_input::State,
   cf_browse_state: button::State,
   cf_open_state: button::State,
+  mmc_candidates: CandidateList,
+  cf_candidates: CandidateList,
+  pick_target_state: pick_list::State<Target>,
+  pick_source_state: pick_list::State<Source>,
   pick_mp_state: pick_list::State<ModPack>,
   link_btn_state: button::State,
+  play_btn_state: button::State,
   unlink_btn_state: button::State,
+  detach_btn_state: button::State,
+  pick_mmc_state: pick_list::State<MMCInstance>,
+  reverse_link_btn_state: button::State,
+  reverse_unlink_btn_state: button::State,
   open_cf_btn_state: button::State,
   open_mmc_btn_state: button::State,
+  logs_btn_state: button::State,
   github_btn_state: button::State,
+  modpacks: Vec<ModPack>,
   selected_mp: Option<ModPack>,
+  /// Mods of the selected pack, along with the pack they were read for
+  inventory: (Option<ModPack>, Vec<ModEntry>),
+  inventory_state: scrollable::State,
+  /// Crash summary of the pack logs were last collected for
+  logs: Option<(ModPack, String)>,
+  logs_state: scrollable::State,
+  /// Id and pack of the running detach job
+  detaching: Option<(u64, ModPack)>,
+  /// Id and pack of the running launch job
+  launching: Option<(u64, ModPack)>,
+  /// Id and zip of the running import job
+  importing: Option<(u64, std::path::PathBuf)>,
+  jobs: u64,
+  mmc_instances: Vec<MMCInstance>,
+  selected_mmc: Option<MMCInstance>,
   info: Option<(Color, String)>,
   flags: Flags,
   settings: ApplicationSettings,
@@ -70,17 +185,34 @@ struct CurseForgeToMultiMC {
 
 #[derive(Debug, Clone)]
 enum Message {
+  TargetPicked(Target),
+  SourcePicked(Source),
   MMCDirectoryChange(String),
   CFDirectoryChange(String),
   MMCBrowse,
+  MMCBrowseOther,
+  MMCCandidatePicked(usize),
   MMCOpen,
   CFBrowse,
+  CFBrowseOther,
+  CFCandidatePicked(usize),
   CFOpen,
   CFMPPicked(ModPack),
   Link,
   Unlink,
+  Detach,
+  DetachProgress(Progress),
+  Detached(std::result::Result<usize, String>),
+  Play,
+  Launched(std::result::Result<String, String>),
+  ImportDropped(std::path::PathBuf),
+  Imported(std::result::Result<String, String>),
+  MMCInstancePicked(MMCInstance),
+  ReverseLink,
+  ReverseUnlink,
   OpenSelectedCF,
   OpenSelectedMMC,
+  CollectLogs,
   OpenGithub,
   Save,
 }
@@ -92,15 +224,13 @@ impl Application for CurseForgeToMultiMC {
 
   fn new(flags: Flags) -> (Self, Command<Message>) {
     let settings = flags.load_settings();
-    let mmc_d = settings.mmc_directory.as_ref()
-      .map(MultiMCDirectory::new)
-      .unwrap_or_default();
+    let mmc_d = settings.mmc_dir();
+    let cf_d = settings.cf_dir();
 
-    let cf_d = settings.cf_directory.as_ref()
-      .map(CurseForgeDirectory::new)
-      .unwrap_or_default();
+    let modpacks = ModPack::list(cf_d.clone(), settings.source, &mut None);
+    let mmc_instances = MMCInstance::list(&mmc_d);
 
-    (Self { mmc_d, cf_d, flags, settings, ..Self::default() }, Command::none())
+    (Self { mmc_d, cf_d, modpacks, mmc_instances, flags, settings, ..Self::default() }, Command::none())
   }
 
   fn title(&self) -> String {
@@ -109,6 +239,23 @@ impl Application for CurseForgeToMultiMC {
 
   fn update(&mut self, message: Message) -> Command<Message> {
     match message {
+      Message::TargetPicked(target) => {
+        if target != self.settings.target {
+          self.settings.target = target;
+          self.mmc_d = MultiMCDirectory::detect(target);
+          self.settings.mmc_directory = Some(self.mmc_d.to_string());
+          self.mmc_candidates.shown = false;
+        }
+      }
+      Message::SourcePicked(source) => {
+        if source != self.settings.source {
+          self.settings.source = source;
+          self.cf_d = CurseForgeDirectory::detect(source);
+          self.settings.cf_directory = Some(self.cf_d.to_string());
+          self.cf_candidates.shown = false;
+          self.selected_mp = None;
+        }
+      }
       Message::MMCDirectoryChange(dir) => {
         self.mmc_d.new_path(&dir);
         self.settings.mmc_directory = Some(dir);
@@ -118,16 +265,36 @@ impl Application for CurseForgeToMultiMC {
         self.settings.cf_directory = Some(dir);
       }
       Message::MMCBrowse => {
+        self.mmc_candidates.show(crate::detect::target_candidates(self.settings.target));
+      }
+      Message::MMCBrowseOther => {
+        self.mmc_candidates.shown = false;
         set_info_if_err!(self.info, self.mmc_d.browse());
         self.settings.mmc_directory = Some(self.mmc_d.to_string());
       }
+      Message::MMCCandidatePicked(index) => {
+        if let Some(candidate) = self.mmc_candidates.pick(index) {
+          self.mmc_d.new_path(&candidate.path);
+          self.settings.mmc_directory = Some(self.mmc_d.to_string());
+        }
+      }
       Message::MMCOpen => {
         set_info_if_err!(self.info, self.mmc_d.open());
       }
       Message::CFBrowse => {
-        set_info_if_err!(self.info, self.mmc_d.browse());
+        self.cf_candidates.show(crate::detect::source_candidates(self.settings.source));
+      }
+      Message::CFBrowseOther => {
+        self.cf_candidates.shown = false;
+        set_info_if_err!(self.info, self.cf_d.browse());
         self.settings.cf_directory = Some(self.cf_d.to_string());
       }
+      Message::CFCandidatePicked(index) => {
+        if let Some(candidate) = self.cf_candidates.pick(index) {
+          self.cf_d.new_path(&candidate.path);
+          self.settings.cf_directory = Some(self.cf_d.to_string());
+        }
+      }
       Message::CFOpen => {
         set_info_if_err!(self.info, self.cf_d.open());
       }
@@ -138,14 +305,18 @@ impl Application for CurseForgeToMultiMC {
       Message::Link => {
         if let Some(selected) = &self.selected_mp {
           let result = crate::link::link(
+            &self.settings,
             self.mmc_d.clone(),
             self.cf_d.clone(),
             selected.clone(),
           );
 
-          self.info = result.as_ref().ok().map(|_| (OK_COLOR, String::from("Linked")));
+          self.info = result.as_ref().ok().map(|warnings| match warnings.is_empty() {
+            true => (OK_COLOR, String::from("Linked")),
+            false => (WARN_COLOR, format!("Linked with warnings: {}", warnings.join("; "))),
+          });
 
-          if let None = self.info {
+          if self.info.is_none() {
             set_info_if_err!(self.info, result.as_ref());
           }
         }
@@ -153,20 +324,99 @@ impl Application for CurseForgeToMultiMC {
       Message::Unlink => {
         if let Some(selected) = &self.selected_mp {
           let result = crate::link::unlink(
+            &self.settings,
             self.mmc_d.clone(),
             selected.clone(),
           );
 
           self.info = result.as_ref().ok().map(|_| (OK_COLOR, String::from("Unlinked")));
 
-          if let None = self.info {
+          if self.info.is_none() {
+            set_info_if_err!(self.info, result.as_ref());
+          }
+        }
+      }
+      Message::Detach => {
+        if let (Some(selected), None) = (&self.selected_mp, &self.detaching) {
+          self.jobs += 1;
+          self.detaching = Some((self.jobs, selected.clone()));
+          self.info = Some((OK_COLOR, String::from("Detaching...")));
+        }
+      }
+      Message::DetachProgress(progress) => {
+        let stage = match progress.stage {
+          Stage::Copying => "Copying",
+          Stage::Verifying => "Verifying",
+        };
+
+        let percent = progress.done * 100 / progress.total.max(1);
+
+        self.info = Some((OK_COLOR, format!("{} {}%", stage, percent)));
+      }
+      Message::Detached(result) => {
+        self.detaching = None;
+        self.info = Some(match result {
+          Ok(copied) => (OK_COLOR, format!("Detached, copied {} files", copied)),
+          Err(err) => (ERR_COLOR, err),
+        });
+      }
+      Message::Play => {
+        if let (Some(selected), None) = (&self.selected_mp, &self.launching) {
+          self.jobs += 1;
+          self.launching = Some((self.jobs, selected.clone()));
+          self.info = Some((OK_COLOR, String::from("Launching...")));
+        }
+      }
+      Message::Launched(result) => {
+        self.launching = None;
+        self.info = Some(match result {
+          Ok(message) => (OK_COLOR, message),
+          Err(err) => (ERR_COLOR, err),
+        });
+      }
+      Message::ImportDropped(path) => {
+        if self.importing.is_none() {
+          self.jobs += 1;
+          self.info = Some((OK_COLOR, format!("Importing {}...", path.display())));
+          self.importing = Some((self.jobs, path));
+        }
+      }
+      Message::Imported(result) => {
+        self.importing = None;
+        self.info = Some(match result {
+          Ok(message) => (OK_COLOR, message),
+          Err(err) => (ERR_COLOR, err),
+        });
+      }
+      Message::MMCInstancePicked(new) => {
+        self.selected_mmc = Some(new);
+        self.info = None;
+      }
+      Message::ReverseLink => {
+        if let Some(selected) = &self.selected_mmc {
+          let result = crate::reverse::reverse_link(selected, &self.cf_d);
+
+          self.info = result.as_ref().ok().map(|_| (OK_COLOR, String::from("Linked to CurseForge")));
+
+          if self.info.is_none() {
+            set_info_if_err!(self.info, result.as_ref());
+          }
+        }
+      }
+      Message::ReverseUnlink => {
+        if let Some(selected) = &self.selected_mmc {
+          let result = crate::reverse::reverse_unlink(selected, &self.cf_d);
+
+          self.info = result.as_ref().ok().map(|_| (OK_COLOR, String::from("Unlinked from CurseForge")));
+
+          if self.info.is_none() {
             set_info_if_err!(self.info, result.as_ref());
           }
         }
       }
       Message::OpenSelectedCF => {
         if let Some(selected) = &self.selected_mp {
-          if let Some(dir) = &selected.cf_dir {
+          if let Some(dir) = &selected.dir {
             let result = open::that(dir);
 
             set_info_if_err!(self.info, result.as_ref());
@@ -182,6 +432,31 @@ impl Application for CurseForgeToMultiMC {
           }
         }
       }
+      Message::CollectLogs => {
+        if let Some(selected) = &self.selected_mp {
+          match CrashLogs::collect(&self.settings, selected) {
+            Ok(logs) => {
+              let file_name = format!("{}-logs.zip", selected);
+              let output = native_dialog::FileDialog::new()
+                .set_filename(&file_name)
+                .add_filter("Zip", &["zip"])
+                .show_save_single_file();
+
+              self.info = match output {
+                Ok(Some(path)) => Some(match logs.write_zip(&path) {
+                  Ok(_) => (OK_COLOR, format!("Saved logs to {}", path.display())),
+                  Err(err) => (ERR_COLOR, err.to_string()),
+                }),
+                Ok(None) => None,
+                Err(err) => Some((ERR_COLOR, err.to_string())),
+              };
+
+              self.logs = Some((selected.clone(), logs.to_string()));
+            }
+            Err(err) => self.info = Some((ERR_COLOR, err.to_string())),
+          }
+        }
+      }
       Message::OpenGithub => {
         let result = open::that(GITHUB_URL);
 
@@ -193,20 +468,105 @@ impl Application for CurseForgeToMultiMC {
       }
     }
 
+    self.modpacks = ModPack::list(self.cf_d.clone(), self.settings.source, &mut self.selected_mp);
+    self.mmc_instances = MMCInstance::list(&self.mmc_d);
+
+    if self.inventory.0 != self.selected_mp {
+      let mods = self.selected_mp.as_ref()
+        .and_then(|it| crate::inventory::inventory(it).ok())
+        .unwrap_or_default();
+
+      self.inventory = (self.selected_mp.clone(), mods);
+    }
+
+    if !self.mmc_instances.iter().any(|it| Some(it) == self.selected_mmc.as_ref()) {
+      self.selected_mmc = None;
+    }
+
     Command::none()
   }
 
   fn subscription(&self) -> Subscription<Message> {
-    iced_native::subscription::events_with(|event, _| {
+    let events = iced_native::subscription::events_with(|event, _| {
       match event {
         Event::Window(iced_native::window::Event::CloseRequested) => Some(Message::Save),
+        Event::Window(iced_native::window::Event::FileDropped(path)) => {
+          let is_zip = path.extension().map(|it| it.eq_ignore_ascii_case("zip")).unwrap_or(false);
+
+          is_zip.then_some(Message::ImportDropped(path))
+        }
         _ => None
       }
-    })
+    });
+
+    let detach = match &self.detaching {
+      Some((id, mp)) => {
+        let id = *id;
+        let settings = self.settings.clone();
+        let mmc = self.mmc_d.clone();
+        let mp = mp.clone();
+
+        Subscription::from_recipe(Job::new(id, move |sender| {
+          let result = crate::detach::detach(&settings, &mmc, &mp, &mut |progress| {
+            let _ = sender.unbounded_send(Message::DetachProgress(progress));
+          });
+
+          let _ = sender.unbounded_send(Message::Detached(result.map_err(|it| it.to_string())));
+        }))
+      }
+      None => Subscription::none(),
+    };
+
+    let launch = match &self.launching {
+      Some((id, mp)) => {
+        let id = *id;
+        let settings = self.settings.clone();
+        let mp = mp.clone();
+
+        Subscription::from_recipe(Job::new(id, move |sender| {
+          let result = crate::launch::launch(&settings, &mp)
+            .map(|it| format!("Launching \"{}\", {}", mp, it))
+            .map_err(|it| it.to_string());
+
+          let _ = sender.unbounded_send(Message::Launched(result));
+        }))
+      }
+      None => Subscription::none(),
+    };
+
+    let import = match &self.importing {
+      Some((id, path)) => {
+        let id = *id;
+        let settings = self.settings.clone();
+        let mmc = self.mmc_d.clone();
+        let path = path.clone();
+
+        Subscription::from_recipe(Job::new(id, move |sender| {
+          let fetcher = crate::import::fetcher(settings.mod_cache.as_deref());
+          let result = crate::import::import(&settings, &mmc, &path, None, fetcher.as_ref())
+            .map(|it| match it.missing.len() {
+              0 => format!("Imported \"{}\"", it.name),
+              missing => format!("Imported \"{}\", {} mods are missing from the mod cache", it.name, missing),
+            })
+            .map_err(|it| it.to_string());
+
+          let _ = sender.unbounded_send(Message::Imported(result));
+        }))
+      }
+      None => Subscription::none(),
+    };
+
+    Subscription::batch([events, detach, launch, import])
   }
 
-  fn view(&mut self) -> Element<Message, Renderer> {
+  fn view(&mut self) -> Element<'_, Message, Renderer> {
     let is_linked = self.selected_mp.clone().unwrap_or_default().is_linked(&self.mmc_d);
+    let has_instance = self.selected_mp.as_ref().and_then(|it| it.mmc_path(&self.mmc_d)).is_some();
+    let target = self.settings.target;
+    let source = self.settings.source;
+    let is_reverse_linked = self.selected_mmc.as_ref()
+      .map(|it| it.is_reverse_linked(&self.cf_d))
+      .unwrap_or(false);
 
     Column::new()
       .padding(20)
@@ -214,7 +574,30 @@ impl Application for CurseForgeToMultiMC {
       .align_items(Alignment::Center)
       .push(
         Row::new()
-          .push(Text::new("MultiMC Directory: "))
+          .align_items(Alignment::Center)
+          .push(Text::new("Link from: "))
+          .push(
+            PickList::new(
+              &mut self.pick_source_state,
+              &Source::ALL[..],
+              Some(source),
+              Message::SourcePicked,
+            )
+          )
+          .push(Space::with_width(Length::Units(12)))
+          .push(Text::new("into: "))
+          .push(
+            PickList::new(
+              &mut self.pick_target_state,
+              &Target::ALL[..],
+              Some(target),
+              Message::TargetPicked,
+            )
+          )
+      )
+      .push(
+        Row::new()
+          .push(Text::new(format!("{} Directory: ", target)))
           .push(
             TextInput::new(
               &mut self.mmc_ti_d_state, "", &self.mmc_d.to_string(),
@@ -230,9 +613,10 @@ impl Application for CurseForgeToMultiMC {
               .on_press(Message::MMCOpen)
           )
       )
+      .push(self.mmc_candidates.view(Message::MMCCandidatePicked, Message::MMCBrowseOther))
       .push(
         Row::new()
-          .push(Text::new("CurseForge Directory: "))
+          .push(Text::new(format!("{} Directory: ", source)))
           .push(
             TextInput::new(
               &mut self.cf_ti_d_state, "", &self.cf_d.to_string(),
@@ -248,10 +632,11 @@ impl Application for CurseForgeToMultiMC {
               .on_press(Message::CFOpen)
           )
       )
+      .push(self.cf_candidates.view(Message::CFCandidatePicked, Message::CFBrowseOther))
       .push(
         PickList::new(
           &mut self.pick_mp_state,
-          ModPack::list(self.cf_d.clone(), &mut self.selected_mp),
+          &self.modpacks[..],
           self.selected_mp.clone(),
           Message::CFMPPicked,
         ).width(Length::Fill)
@@ -261,16 +646,23 @@ impl Application for CurseForgeToMultiMC {
           .push(
             Button::new(
               &mut self.open_cf_btn_state,
-              Text::new("Open CurseForge Instance"),
+              Text::new(format!("Open {} Instance", source)),
             ).on_press_if(Message::OpenSelectedCF, self.selected_mp.is_some())
           )
           .push(Space::with_width(Length::Units(12)))
           .push(
             Button::new(
               &mut self.open_mmc_btn_state,
-              Text::new("Open MultiMC Instance"),
+              Text::new(format!("Open {} Instance", target)),
             ).on_press_if(Message::OpenSelectedMMC, is_linked)
           )
+          .push(Space::with_width(Length::Units(12)))
+          .push(
+            Button::new(
+              &mut self.logs_btn_state,
+              Text::new("Collect Logs"),
+            ).on_press_if(Message::CollectLogs, self.selected_mp.is_some())
+          )
       )
       .push(
         Row::new()
@@ -287,6 +679,83 @@ impl Application for CurseForgeToMultiMC {
               Text::new("Unlink"),
             ).on_press_if(Message::Unlink, is_linked)
           )
+          .push(Space::with_width(Length::Units(12)))
+          .push(
+            Button::new(
+              &mut self.detach_btn_state,
+              Text::new("Detach"),
+            ).on_press_if(Message::Detach, is_linked && self.detaching.is_none())
+          )
+          .push(Space::with_width(Length::Units(12)))
+          .push(
+            Button::new(
+              &mut self.play_btn_state,
+              Text::new("Play"),
+            ).on_press_if(Message::Play, has_instance && self.launching.is_none())
+          )
+      )
+      .push::<Element<Message, Renderer>>(
+        match self.inventory.1.is_empty() {
+          true => Space::with_height(Length::Units(0)).into(),
+          false => {
+            let manual = self.inventory.1.iter().filter(|it| it.manual).count();
+
+            self.inventory.1.iter()
+              .fold(
+                Scrollable::new(&mut self.inventory_state)
+                  .width(Length::Fill)
+                  .height(Length::Units(160))
+                  .push(Text::new(format!("{} mods, {} manually added", self.inventory.1.len(), manual))),
+                |scrollable, entry| scrollable.push(Text::new(entry.to_string()).size(16)),
+              )
+              .into()
+          }
+        }
+      )
+      .push::<Element<Message, Renderer>>(
+        match &self.logs {
+          Some((mp, summary)) if Some(mp) == self.selected_mp.as_ref() => {
+            summary.lines()
+              .fold(
+                Scrollable::new(&mut self.logs_state)
+                  .width(Length::Fill)
+                  .height(Length::Units(160)),
+                |scrollable, line| scrollable.push(Text::new(line).size(16)),
+              )
+              .into()
+          }
+          _ => Space::with_height(Length::Units(0)).into(),
+        }
+      )
+      .push(Space::with_height(Length::Units(12)))
+      .push(
+        Row::new()
+          .align_items(Alignment::Center)
+          .push(Text::new(format!("{} instance to show in CurseForge: ", target)))
+          .push(
+            PickList::new(
+              &mut self.pick_mmc_state,
+              &self.mmc_instances[..],
+              self.selected_mmc.clone(),
+              Message::MMCInstancePicked,
+            ).width(Length::Fill)
+          )
+      )
+      .push(
+        Row::new()
+          .push(
+            Button::new(
+              &mut self.reverse_link_btn_state,
+              Text::new("Link to CurseForge"),
+            ).on_press_if(Message::ReverseLink, self.selected_mmc.is_some() && !is_reverse_linked)
+          )
+          .push(Space::with_width(Length::Units(12)))
+          .push(
+            Button::new(
+              &mut self.reverse_unlink_btn_state,
+              Text::new("Unlink from CurseForge"),
+            ).on_press_if(Message::ReverseUnlink, is_reverse_linked)
+          )
       )
       .push(
         Button::new(
@@ -299,6 +768,7 @@ impl Application for CurseForgeToMultiMC {
         Text::new("Application may be slow to respond, I do not know how to fix this, blame windows I guess")
           .size(28)
       )
+      .push(Text::new("Drop a CurseForge modpack zip on this window to import it"))
       .push(Space::new(Length::Fill, Length::Fill))
       .push::<Element<Message, Renderer>>(
         match &self.info {
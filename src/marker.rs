@@ -0,0 +1,96 @@
+use std::fs::{File, remove_file};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::link::Result;
+
+/// Written into every directory this application creates, so unlinking never removes anything it doesn't own
+pub const MARKER_FILE: &str = ".curseforge_to_multimc.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerKind {
+  /// A MultiMC instance whose game directory is a symlink to a source instance
+  Link,
+  /// A MultiMC game directory exposed as a CurseForge instance
+  ReverseLink,
+  /// A linked instance whose symlink was replaced by a copy of the source instance
+  Detached,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Marker {
+  pub kind: MarkerKind,
+  /// Where the symlink points to, or pointed to before detaching
+  pub source: PathBuf,
+  /// Application version that created the marker
+  pub version: String,
+}
+
+impl Marker {
+  pub fn new(kind: MarkerKind, source: &Path) -> Self {
+    Self {
+      kind,
+      source: source.to_path_buf(),
+      version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+  }
+
+  pub fn read(dir: &Path) -> Option<Marker> {
+    serde_json::from_reader(File::open(dir.join(MARKER_FILE)).ok()?).ok()
+  }
+
+  pub fn write(&self, dir: &Path) -> Result<()> {
+    serde_json::to_writer_pretty(File::create(dir.join(MARKER_FILE))?, self)?;
+
+    Ok(())
+  }
+
+  pub fn remove(dir: &Path) -> Result<()> {
+    let path = dir.join(MARKER_FILE);
+
+    if path.exists() {
+      remove_file(path)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Whether `dir` was created by this application as `kind`
+pub fn is_marked(dir: &Path, kind: MarkerKind) -> bool {
+  Marker::read(dir).map(|it| it.kind == kind).unwrap_or(false)
+}
+
+/// Whether `link` is a symlink pointing to `target`, used for links made before markers existed
+pub fn is_symlink_to(link: &Path, target: &Path) -> bool {
+  let is_symlink = link.symlink_metadata().map(|it| it.file_type().is_symlink()).unwrap_or(false);
+
+  // A dangling link canonicalizes to nothing, just like a missing target
+  match (link.canonicalize(), target.canonicalize()) {
+    (Ok(link), Ok(target)) => is_symlink && link == target,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn dangling_links_point_nowhere() {
+    let dir = std::env::temp_dir().join(format!("marker-{}-dangling", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+
+    symlink::symlink_dir(dir.join("target"), dir.join("link")).unwrap();
+    assert!(is_symlink_to(&dir.join("link"), &dir.join("target")));
+
+    std::fs::remove_dir(dir.join("target")).unwrap();
+    assert!(!is_symlink_to(&dir.join("link"), &dir.join("target")));
+    assert!(!is_symlink_to(&dir.join("missing"), &dir.join("target")));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -1,8 +1,13 @@
-use clap::Arg;
+use clap::{Arg, ArgMatches};
 use iced::window::Icon;
 use serde::{Deserialize, Serialize};
 
 use crate::{NAME, TITLE};
+use crate::directories::{CurseForgeDirectory, Directory, MultiMCDirectory};
+use crate::launcher::Target;
+use crate::servers::Server;
+use crate::shared::SharePolicy;
+use crate::source::Source;
 
 pub type AnyResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -12,9 +17,8 @@ fn gen_icon() -> AnyResult<Icon> {
   let image = ::image::load_from_memory(SOURCE)?;
   let image = image.to_rgba8();
   let bytes = image.pixels()
-    .map(|it| it.0.iter())
-    .flatten()
-    .map(|it| *it)
+    .flat_map(|it| it.0.iter())
+    .copied()
     .collect::<Vec<_>>();
 
   let icon = Icon::from_rgba(bytes, 256, 256)?;
@@ -23,7 +27,7 @@ fn gen_icon() -> AnyResult<Icon> {
 }
 
 pub fn icon() -> std::result::Result<Icon, iced::Error> {
-  gen_icon().map_err(|it| iced::Error::WindowCreationFailed(it))
+  gen_icon().map_err(iced::Error::WindowCreationFailed)
 }
 
 // Need this to make CLI work, but will still hide console when ran normally (double clicking, start menu, etc)
@@ -46,6 +50,8 @@ pub fn hide_console() {
 #[derive(Default, Clone)]
 pub struct Flags {
   pub settings_path: Option<String>,
+  /// Subcommand to run instead of opening the window
+  pub command: Option<(String, ArgMatches<'static>)>,
 }
 
 impl Flags {
@@ -60,11 +66,16 @@ impl Flags {
           .value_name("FILE")
           .help("Custom settings path (settings format is TOML)")
           .takes_value(true)
-      )
-      .get_matches();
+          .global(true)
+      );
+
+    let matches = crate::cli::subcommands(matches).get_matches();
+    let command = matches.subcommand_name()
+      .and_then(|name| Some((name.to_string(), matches.subcommand_matches(name)?.clone())));
 
     Self {
-      settings_path: matches.value_of("settings").map(str::to_string)
+      settings_path: matches.value_of("settings").map(str::to_string),
+      command,
     }
   }
 
@@ -85,8 +96,114 @@ impl Flags {
   }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ApplicationSettings {
   pub mmc_directory: Option<String>,
   pub cf_directory: Option<String>,
+  /// Launcher instances are linked from
+  pub source: Source,
+  /// Launcher instances are linked into
+  pub target: Target,
+  /// Group linked instances are added to, none when empty
+  pub group: Option<String>,
+  /// Local directory mods are taken from when importing modpacks
+  pub mod_cache: Option<String>,
+  /// Makes newly linked instances run `check` before every launch
+  pub check_before_launch: bool,
+  pub backups: BackupSettings,
+  pub shared: SharedFolders,
+  pub options: OptionsSync,
+  pub servers: ServerSync,
+  /// Target launcher executable used to launch instances, detected when not set
+  pub launcher_executable: Option<String>,
+  /// Gives linked instances a desktop entry on Linux, so they show up in the application menu
+  pub desktop_shortcuts: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+  /// Zips `saves` before unlinking, detaching or anything else that deletes or replaces instance data
+  pub enabled: bool,
+  /// Backups kept per instance, older ones are removed. 0 keeps all of them
+  pub keep: usize,
+  /// Where backups are saved, defaults to the application's data directory
+  pub directory: Option<String>,
+}
+
+impl Default for BackupSettings {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      keep: 5,
+      directory: None,
+    }
+  }
+}
+
+/// Folders linked instances share with each other, set up when linking and undone when unlinking
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharedFolders {
+  pub resourcepacks: SharePolicy,
+  pub shaderpacks: SharePolicy,
+  pub screenshots: SharePolicy,
+  /// Where the shared folders are, defaults to the application's data directory
+  pub directory: Option<String>,
+}
+
+impl SharedFolders {
+  pub fn policies(&self) -> [(&'static str, SharePolicy); 3] {
+    [
+      ("resourcepacks", self.resourcepacks),
+      ("shaderpacks", self.shaderpacks),
+      ("screenshots", self.screenshots),
+    ]
+  }
+}
+
+/// Template `options.txt`, `optionsof.txt` and `optionsshaders.txt` merged into linked instances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptionsSync {
+  /// Directory with the template files, defaults to the application's data directory
+  pub template: Option<String>,
+  /// Keys the template overrides in instances that already set them, `*` matches anything. Other keys are
+  /// only added where they're missing
+  pub keys: Vec<String>,
+}
+
+impl Default for OptionsSync {
+  fn default() -> Self {
+    Self {
+      template: None,
+      keys: vec![String::from("key_*")],
+    }
+  }
+}
+
+/// Servers added to the multiplayer list of linked instances
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSync {
+  pub list: Vec<Server>,
+  /// Keeps these servers at the top of the list, in this order
+  pub pin: bool,
+}
+
+impl ApplicationSettings {
+  /// Configured target instance directory, detected when not set
+  pub fn mmc_dir(&self) -> MultiMCDirectory {
+    self.mmc_directory.as_ref()
+      .map(MultiMCDirectory::new)
+      .unwrap_or_else(|| MultiMCDirectory::detect(self.target))
+  }
+
+  /// Configured source instance directory, detected when not set
+  pub fn cf_dir(&self) -> CurseForgeDirectory {
+    self.cf_directory.as_ref()
+      .map(CurseForgeDirectory::new)
+      .unwrap_or_else(|| CurseForgeDirectory::detect(self.source))
+  }
 }
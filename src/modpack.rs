@@ -1,18 +1,27 @@
 use std::fmt::Formatter;
 use std::path::PathBuf;
 
-use crate::directories::{CurseForgeDirectory, Directory, MultiMCDirectory};
-use crate::link::get_cf_instance;
+use crate::directories::{check_name, CurseForgeDirectory, Directory, MultiMCDirectory};
+use crate::link::Result;
+use crate::marker::{is_marked, MarkerKind};
+use crate::source::{Source, SourceInstance};
 
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct ModPack {
-  pub cf_dir: Option<PathBuf>,
+  pub dir: Option<PathBuf>,
+  pub source: Source,
+  /// Instance name as shown by the source launcher, read once when listing
+  pub name: String,
 }
 
 impl std::fmt::Display for ModPack {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if !self.name.is_empty() {
+      return f.write_str(&self.name);
+    }
+
     f.write_str(
-      self.cf_dir
+      self.dir
         .clone().unwrap_or_default()
         .file_name().unwrap_or_default()
         .to_str().unwrap_or_default()
@@ -23,13 +32,29 @@ impl std::fmt::Display for ModPack {
 impl Eq for ModPack {}
 
 impl ModPack {
-  pub fn cf_path(&self) -> PathBuf {
-    self.cf_dir.clone().unwrap_or_default()
+  pub fn new(dir: PathBuf, source: Source) -> Self {
+    let name = source.launcher().read(&dir).map(|it| it.name).unwrap_or_default();
+
+    Self { dir: Some(dir), source, name }
+  }
+
+  pub fn path(&self) -> PathBuf {
+    self.dir.clone().unwrap_or_default()
+  }
+
+  pub fn instance(&self) -> Result<SourceInstance> {
+    self.source.launcher().read(&self.path())
   }
 
   pub fn mmc_path(&self, mmc: &MultiMCDirectory) -> Option<PathBuf> {
-    let instance = get_cf_instance(self).ok()?;
-    let path = mmc.path.join(&instance.name);
+    let name = match self.name.is_empty() {
+      true => self.instance().ok()?.name,
+      false => self.name.clone(),
+    };
+
+    check_name(&name).ok()?;
+
+    let path = mmc.path.join(name);
 
     if path.exists() {
       Some(path)
@@ -39,21 +64,35 @@ impl ModPack {
   }
 
   pub fn is_linked(&self, mmc: &MultiMCDirectory) -> bool {
-    self.mmc_path(mmc).is_some()
+    self.mmc_path(mmc).map(|it| !is_marked(&it, MarkerKind::Detached)).unwrap_or(false)
   }
 
-  pub fn list(cf: CurseForgeDirectory, selected: &mut Option<ModPack>) -> Vec<ModPack> {
+  pub fn list(cf: CurseForgeDirectory, source: Source, selected: &mut Option<ModPack>) -> Vec<ModPack> {
     let path = cf.path();
 
-    path
-      .read_dir()
-      .map(|it| it
-        .map(|it| ModPack {
-          cf_dir: it.map(|it| it.path()).ok()
-        })
-        .collect::<Vec<_>>()
-      )
-      .map_err(|_| *selected = None)
-      .unwrap_or_default()
-  }
-}
\ No newline at end of file
+    if !path.is_dir() {
+      *selected = None;
+    }
+
+    source.launcher()
+      .discover(path)
+      .into_iter()
+      .filter(|it| !is_marked(it, MarkerKind::ReverseLink))
+      .map(|it| ModPack::new(it, source))
+      .collect()
+  }
+
+  /// Finds a pack by its name, directory name or path
+  pub fn find(cf: CurseForgeDirectory, source: Source, query: &str) -> Option<ModPack> {
+    let path = PathBuf::from(query).canonicalize().ok();
+
+    ModPack::list(cf, source, &mut None)
+      .into_iter()
+      .find(|it| {
+        it.name == query
+          || it.to_string() == query
+          || it.path().file_name().map(|it| it == query).unwrap_or(false)
+          || path.is_some() && it.path().canonicalize().ok() == path
+      })
+  }
+}
@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::link::Result;
+use crate::source::ModLoader;
+
+/// `modrinth.index.json` at the root of a `.mrpack`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthIndex {
+  #[serde(rename = "formatVersion")] pub format_version: u32,
+  pub game: String,
+  #[serde(rename = "versionId")] pub version_id: String,
+  pub name: String,
+  #[serde(skip_serializing_if = "Option::is_none", default)] pub summary: Option<String>,
+  pub files: Vec<ModrinthFile>,
+  /// `minecraft` and the loader, e.g. `forge` or `fabric-loader`, mapped to their versions
+  pub dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthFile {
+  /// Relative to the game directory
+  pub path: String,
+  /// `sha1` and `sha512`
+  pub hashes: BTreeMap<String, String>,
+  pub downloads: Vec<String>,
+  #[serde(rename = "fileSize")] pub file_size: u64,
+}
+
+/// Hosts the `.mrpack` format allows in `downloads`, Modrinth rejects packs pointing anywhere else
+pub const ALLOWED_HOSTS: [&str; 4] = ["cdn.modrinth.com", "github.com", "raw.githubusercontent.com", "gitlab.com"];
+
+/// Whether `url` is an `https` URL on one of the `ALLOWED_HOSTS`
+pub fn is_allowed_download(url: &str) -> bool {
+  let host = url.strip_prefix("https://")
+    .and_then(|it| it.split(['/', '?', '#']).next())
+    .map(|it| it.rsplit_once('@').map(|it| it.1).unwrap_or(it))
+    .map(|it| it.split(':').next().unwrap_or_default().to_lowercase());
+
+  host.map(|host| ALLOWED_HOSTS.contains(&host.as_str())).unwrap_or(false)
+}
+
+/// Dependency key Modrinth uses for `loader`
+pub fn loader_dependency(loader: &ModLoader) -> Option<&'static str> {
+  match loader {
+    ModLoader::Forge(_) => Some("forge"),
+    ModLoader::NeoForge(_) => Some("neoforge"),
+    ModLoader::Fabric(_) => Some("fabric-loader"),
+    ModLoader::Quilt(_) => Some("quilt-loader"),
+    ModLoader::Vanilla => None,
+  }
+}
+
+impl ModrinthIndex {
+  pub fn dependencies(mc_version: &str, loader: &ModLoader) -> BTreeMap<String, String> {
+    let mut dependencies = BTreeMap::new();
+
+    dependencies.insert(String::from("minecraft"), mc_version.to_string());
+
+    if let (Some(key), Some(version)) = (loader_dependency(loader), loader.version()) {
+      dependencies.insert(key.to_string(), version.to_string());
+    }
+
+    dependencies
+  }
+}
+
+/// Modrinth asks API clients to identify themselves
+const USER_AGENT: &str = concat!("Ricky12Awesome/curseforge_to_multimc/", env!("CARGO_PKG_VERSION"));
+
+/// Finds where Modrinth hosts files, by their sha1
+pub trait ModrinthResolver {
+  /// Download URLs of the `sha1s` Modrinth knows about, keyed by hash
+  fn resolve(&self, sha1s: &[String]) -> Result<HashMap<String, String>>;
+}
+
+/// Resolves nothing, every file that isn't known locally is bundled
+pub struct Offline;
+
+impl ModrinthResolver for Offline {
+  fn resolve(&self, _sha1s: &[String]) -> Result<HashMap<String, String>> {
+    Ok(HashMap::new())
+  }
+}
+
+/// Looks files up with Modrinth's `version_files` endpoint
+pub struct ModrinthApi;
+
+#[derive(Deserialize)]
+struct ModrinthVersion {
+  files: Vec<ModrinthVersionFile>,
+}
+
+#[derive(Deserialize)]
+struct ModrinthVersionFile {
+  url: String,
+  #[serde(default)] hashes: BTreeMap<String, String>,
+}
+
+impl ModrinthVersion {
+  /// URL of the file with `sha1`, if the version has one and it's somewhere `.mrpack` can download from
+  fn url(&self, sha1: &str) -> Option<String> {
+    self.files.iter()
+      .find(|it| it.hashes.get("sha1").map(|it| it == sha1).unwrap_or(false))
+      .map(|it| it.url.clone())
+      .filter(|it| is_allowed_download(it))
+  }
+}
+
+impl ModrinthResolver for ModrinthApi {
+  fn resolve(&self, sha1s: &[String]) -> Result<HashMap<String, String>> {
+    if sha1s.is_empty() {
+      return Ok(HashMap::new());
+    }
+
+    let body = serde_json::json!({ "hashes": sha1s, "algorithm": "sha1" });
+    let response = ureq::AgentBuilder::new()
+      .timeout(Duration::from_secs(30))
+      .build()
+      .post("https://api.modrinth.com/v2/version_files")
+      .set("User-Agent", USER_AGENT)
+      .set("Content-Type", "application/json")
+      .send_string(&body.to_string())?;
+    let versions = serde_json::from_reader::<_, HashMap<String, ModrinthVersion>>(response.into_reader())?;
+
+    Ok(versions.iter().filter_map(|(sha1, version)| Some((sha1.clone(), version.url(sha1)?))).collect())
+  }
+}
+
+#[derive(Deserialize)]
+struct ModrinthProfileProjects {
+  #[serde(default)] projects: HashMap<String, ModrinthProject>,
+}
+
+#[derive(Deserialize)]
+struct ModrinthProject {
+  metadata: ModrinthProjectMetadata,
+}
+
+#[derive(Deserialize)]
+struct ModrinthProjectMetadata {
+  version: Option<ModrinthVersion>,
+}
+
+/// Download URLs the Modrinth App recorded in a `profile.json` for the files it installed, keyed by sha1.
+/// Files it couldn't match to a Modrinth version have no URL
+pub fn profile_downloads(profile: &Path) -> HashMap<String, String> {
+  let projects = File::open(profile).ok()
+    .and_then(|it| serde_json::from_reader::<_, ModrinthProfileProjects>(it).ok())
+    .map(|it| it.projects)
+    .unwrap_or_default();
+
+  projects.into_values()
+    .filter_map(|it| it.metadata.version)
+    .flat_map(|it| it.files)
+    .filter(|it| is_allowed_download(&it.url))
+    .filter_map(|it| Some((it.hashes.get("sha1")?.clone(), it.url)))
+    .collect()
+}
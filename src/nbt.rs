@@ -0,0 +1,328 @@
+use std::io::{Read, Write};
+
+use crate::link::Result;
+
+/// Compounds and lists nested deeper than this are rejected, Minecraft uses the same limit
+const MAX_DEPTH: usize = 512;
+
+/// A named binary tag as Minecraft stores it, big endian and uncompressed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+  Byte(i8),
+  Short(i16),
+  Int(i32),
+  Long(i64),
+  Float(f32),
+  Double(f64),
+  ByteArray(Vec<i8>),
+  String(String),
+  /// Type ID of the elements, kept so empty lists are written back the way they were read
+  List(u8, Vec<Tag>),
+  /// Entries in the order they were read
+  Compound(Vec<(String, Tag)>),
+  IntArray(Vec<i32>),
+  LongArray(Vec<i64>),
+}
+
+impl Tag {
+  pub fn id(&self) -> u8 {
+    match self {
+      Tag::Byte(_) => 1,
+      Tag::Short(_) => 2,
+      Tag::Int(_) => 3,
+      Tag::Long(_) => 4,
+      Tag::Float(_) => 5,
+      Tag::Double(_) => 6,
+      Tag::ByteArray(_) => 7,
+      Tag::String(_) => 8,
+      Tag::List(_, _) => 9,
+      Tag::Compound(_) => 10,
+      Tag::IntArray(_) => 11,
+      Tag::LongArray(_) => 12,
+    }
+  }
+
+  /// Entry `name` of a compound
+  pub fn get(&self, name: &str) -> Option<&Tag> {
+    match self {
+      Tag::Compound(entries) => entries.iter().find(|it| it.0 == name).map(|it| &it.1),
+      _ => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Tag::String(it) => Some(it),
+      _ => None,
+    }
+  }
+}
+
+fn read_bytes<const N: usize>(reader: &mut dyn Read) -> Result<[u8; N]> {
+  let mut buf = [0; N];
+  reader.read_exact(&mut buf)?;
+
+  Ok(buf)
+}
+
+fn read_len(reader: &mut dyn Read) -> Result<usize> {
+  let len = i32::from_be_bytes(read_bytes(reader)?);
+
+  usize::try_from(len).map_err(|_| format!("Negative length {}", len).into())
+}
+
+/// Decodes Java's modified UTF-8, which stores null as `C0 80` and characters outside the BMP as two
+/// 3 byte surrogates. Unpaired surrogates become U+FFFD as Rust strings can't hold them
+fn decode_modified_utf8(bytes: &[u8]) -> Result<String> {
+  let invalid = || Box::<dyn std::error::Error>::from("Invalid modified UTF-8 in NBT string");
+  let mut units = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+
+  while index < bytes.len() {
+    let continuation = |offset: usize| {
+      bytes.get(index + offset).filter(|it| *it & 0xc0 == 0x80).map(|it| (*it & 0x3f) as u16).ok_or_else(invalid)
+    };
+
+    let (unit, len) = match bytes[index] {
+      // Java reads a plain 0 byte too, it just never writes one
+      byte @ 0x00..=0x7f => (byte as u16, 1),
+      byte @ 0xc0..=0xdf => (((byte & 0x1f) as u16) << 6 | continuation(1)?, 2),
+      byte @ 0xe0..=0xef => (((byte & 0x0f) as u16) << 12 | continuation(1)? << 6 | continuation(2)?, 3),
+      _ => return Err(invalid()),
+    };
+
+    units.push(unit);
+    index += len;
+  }
+
+  Ok(char::decode_utf16(units).map(|it| it.unwrap_or(char::REPLACEMENT_CHARACTER)).collect())
+}
+
+/// Encodes `value` as Java's modified UTF-8, the inverse of `decode_modified_utf8`
+fn encode_modified_utf8(value: &str) -> Vec<u8> {
+  let mut bytes = Vec::with_capacity(value.len());
+
+  for unit in value.encode_utf16() {
+    match unit {
+      0x01..=0x7f => bytes.push(unit as u8),
+      0x00 | 0x80..=0x7ff => bytes.extend([0xc0 | (unit >> 6) as u8, 0x80 | (unit & 0x3f) as u8]),
+      _ => bytes.extend([0xe0 | (unit >> 12) as u8, 0x80 | ((unit >> 6) & 0x3f) as u8, 0x80 | (unit & 0x3f) as u8]),
+    }
+  }
+
+  bytes
+}
+
+fn read_string(reader: &mut dyn Read) -> Result<String> {
+  let len = u16::from_be_bytes(read_bytes(reader)?);
+  let mut buf = vec![0; len as usize];
+  reader.read_exact(&mut buf)?;
+
+  decode_modified_utf8(&buf)
+}
+
+fn read_payload(reader: &mut dyn Read, id: u8, depth: usize) -> Result<Tag> {
+  if depth > MAX_DEPTH {
+    return Err(Box::from("NBT is nested too deep"));
+  }
+
+  let tag = match id {
+    1 => Tag::Byte(i8::from_be_bytes(read_bytes(reader)?)),
+    2 => Tag::Short(i16::from_be_bytes(read_bytes(reader)?)),
+    3 => Tag::Int(i32::from_be_bytes(read_bytes(reader)?)),
+    4 => Tag::Long(i64::from_be_bytes(read_bytes(reader)?)),
+    5 => Tag::Float(f32::from_be_bytes(read_bytes(reader)?)),
+    6 => Tag::Double(f64::from_be_bytes(read_bytes(reader)?)),
+    7 => {
+      let len = read_len(reader)?;
+      let mut buf = Vec::new();
+
+      // Grows as it reads, so a corrupt length can't allocate more than the file has
+      if (&mut *reader).take(len as u64).read_to_end(&mut buf)? != len {
+        return Err(Box::from("NBT ends in the middle of a byte array"));
+      }
+
+      Tag::ByteArray(buf.into_iter().map(|it| it as i8).collect())
+    }
+    8 => Tag::String(read_string(reader)?),
+    9 => {
+      let element = read_bytes::<1>(reader)?[0];
+      let len = read_len(reader)?;
+      let elements = (0..len)
+        .map(|_| read_payload(reader, element, depth + 1))
+        .collect::<Result<Vec<_>>>()?;
+
+      Tag::List(element, elements)
+    }
+    10 => {
+      let mut entries = Vec::new();
+
+      loop {
+        let id = read_bytes::<1>(reader)?[0];
+
+        if id == 0 {
+          break;
+        }
+
+        let name = read_string(reader)?;
+        entries.push((name, read_payload(reader, id, depth + 1)?));
+      }
+
+      Tag::Compound(entries)
+    }
+    11 => {
+      let len = read_len(reader)?;
+      Tag::IntArray((0..len).map(|_| Ok(i32::from_be_bytes(read_bytes(reader)?))).collect::<Result<_>>()?)
+    }
+    12 => {
+      let len = read_len(reader)?;
+      Tag::LongArray((0..len).map(|_| Ok(i64::from_be_bytes(read_bytes(reader)?))).collect::<Result<_>>()?)
+    }
+    _ => return Err(format!("Unknown NBT tag type {}", id).into()),
+  };
+
+  Ok(tag)
+}
+
+/// Reads the root tag and its name
+pub fn read(reader: &mut dyn Read) -> Result<(String, Tag)> {
+  let id = read_bytes::<1>(reader)?[0];
+  let name = read_string(reader)?;
+
+  Ok((name, read_payload(reader, id, 0)?))
+}
+
+fn write_string(writer: &mut dyn Write, value: &str) -> Result<()> {
+  let bytes = encode_modified_utf8(value);
+  let len = u16::try_from(bytes.len()).map_err(|_| "String too long for NBT")?;
+
+  writer.write_all(&len.to_be_bytes())?;
+  writer.write_all(&bytes)?;
+
+  Ok(())
+}
+
+fn write_len(writer: &mut dyn Write, len: usize) -> Result<()> {
+  let len = i32::try_from(len).map_err(|_| "Array too long for NBT")?;
+  writer.write_all(&len.to_be_bytes())?;
+
+  Ok(())
+}
+
+fn write_payload(writer: &mut dyn Write, tag: &Tag) -> Result<()> {
+  match tag {
+    Tag::Byte(it) => writer.write_all(&it.to_be_bytes())?,
+    Tag::Short(it) => writer.write_all(&it.to_be_bytes())?,
+    Tag::Int(it) => writer.write_all(&it.to_be_bytes())?,
+    Tag::Long(it) => writer.write_all(&it.to_be_bytes())?,
+    Tag::Float(it) => writer.write_all(&it.to_be_bytes())?,
+    Tag::Double(it) => writer.write_all(&it.to_be_bytes())?,
+    Tag::ByteArray(values) => {
+      write_len(writer, values.len())?;
+      writer.write_all(&values.iter().map(|it| *it as u8).collect::<Vec<_>>())?;
+    }
+    Tag::String(it) => write_string(writer, it)?,
+    Tag::List(element, values) => {
+      let element = values.first().map(Tag::id).unwrap_or(*element);
+
+      writer.write_all(&[element])?;
+      write_len(writer, values.len())?;
+
+      for value in values {
+        write_payload(writer, value)?;
+      }
+    }
+    Tag::Compound(entries) => {
+      for (name, value) in entries {
+        writer.write_all(&[value.id()])?;
+        write_string(writer, name)?;
+        write_payload(writer, value)?;
+      }
+
+      writer.write_all(&[0])?;
+    }
+    Tag::IntArray(values) => {
+      write_len(writer, values.len())?;
+
+      for value in values {
+        writer.write_all(&value.to_be_bytes())?;
+      }
+    }
+    Tag::LongArray(values) => {
+      write_len(writer, values.len())?;
+
+      for value in values {
+        writer.write_all(&value.to_be_bytes())?;
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Writes `tag` as the root tag called `name`
+pub fn write(writer: &mut dyn Write, name: &str, tag: &Tag) -> Result<()> {
+  writer.write_all(&[tag.id()])?;
+  write_string(writer, name)?;
+  write_payload(writer, tag)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip(tag: &Tag) -> (String, Tag) {
+    let mut bytes = Vec::new();
+    write(&mut bytes, "root", tag).unwrap();
+
+    read(&mut bytes.as_slice()).unwrap()
+  }
+
+  #[test]
+  fn every_tag_round_trips() {
+    let tag = Tag::Compound(vec![
+      (String::from("byte"), Tag::Byte(-1)),
+      (String::from("short"), Tag::Short(-300)),
+      (String::from("int"), Tag::Int(1 << 20)),
+      (String::from("long"), Tag::Long(-1 << 40)),
+      (String::from("float"), Tag::Float(1.5)),
+      (String::from("double"), Tag::Double(-2.25)),
+      (String::from("bytes"), Tag::ByteArray(vec![-128, 0, 127])),
+      (String::from("string"), Tag::String(String::from("Server \u{1F3AE} caf\u{e9} \0 end"))),
+      (String::from("empty"), Tag::List(10, Vec::new())),
+      (String::from("list"), Tag::List(8, vec![Tag::String(String::from("a")), Tag::String(String::from("b"))])),
+      (String::from("nested"), Tag::Compound(vec![(String::from("\u{1F600}"), Tag::Byte(1))])),
+      (String::from("ints"), Tag::IntArray(vec![i32::MIN, 0, i32::MAX])),
+      (String::from("longs"), Tag::LongArray(vec![i64::MIN, i64::MAX])),
+    ]);
+
+    assert_eq!(round_trip(&tag), (String::from("root"), tag));
+  }
+
+  #[test]
+  fn strings_use_modified_utf8() {
+    assert_eq!(encode_modified_utf8("\0"), [0xc0, 0x80]);
+    assert_eq!(encode_modified_utf8("\u{1F600}"), [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+    assert_eq!(encode_modified_utf8("caf\u{e9}"), "caf\u{e9}".as_bytes());
+    assert_eq!(decode_modified_utf8(&[0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, 0xc0, 0x80]).unwrap(), "\u{1F600}\0");
+    assert!(decode_modified_utf8(&[0xf0, 0x9f, 0x98, 0x80]).is_err());
+  }
+
+  /// A root list holding `depth` lists nested into each other
+  fn nested_lists(depth: usize) -> Vec<u8> {
+    let mut bytes = vec![9, 0, 0];
+
+    for _ in 0..depth {
+      bytes.extend([9, 0, 0, 0, 1]);
+    }
+
+    bytes.extend([0, 0, 0, 0, 0]);
+    bytes
+  }
+
+  #[test]
+  fn deep_nesting_is_rejected() {
+    assert!(read(&mut nested_lists(MAX_DEPTH).as_slice()).is_ok());
+    assert!(read(&mut nested_lists(MAX_DEPTH + 1).as_slice()).is_err());
+  }
+}
@@ -0,0 +1,211 @@
+use std::cmp::Ordering;
+use std::fs::{copy, create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use crate::export::matches;
+use crate::launcher::game_dir;
+use crate::link::Result;
+use crate::marker::{is_marked, is_symlink_to, MarkerKind};
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+use crate::state::data_dir;
+use crate::version::compare;
+
+/// Files the template can have, along with what separates keys from values in them
+pub const OPTIONS_FILES: [(&str, char); 3] = [
+  ("options.txt", ':'),
+  ("optionsof.txt", ':'),
+  ("optionsshaders.txt", '='),
+];
+
+/// Never taken from the template, Minecraft uses it to upgrade the rest of the file
+const VERSION_KEY: &str = "version";
+
+/// Where the template options files are
+pub fn template_dir(settings: &ApplicationSettings) -> PathBuf {
+  settings.options.template.as_ref()
+    .map(PathBuf::from)
+    .unwrap_or_else(|| data_dir().join("options"))
+}
+
+fn parse(text: &str, separator: char) -> Vec<(String, String)> {
+  text.lines()
+    .filter_map(|it| it.split_once(separator))
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
+fn format(options: &[(String, String)], separator: char) -> String {
+  options.iter()
+    .map(|(key, value)| format!("{}{}{}\n", key, separator, value))
+    .collect()
+}
+
+/// LWJGL 2 key codes used before 1.13 and the key names used since
+fn key_codes() -> Vec<(i32, String)> {
+  let mut codes = vec![
+    (0, "key.keyboard.unknown"), (1, "key.keyboard.escape"), (11, "key.keyboard.0"),
+    (12, "key.keyboard.minus"), (13, "key.keyboard.equal"), (14, "key.keyboard.backspace"),
+    (15, "key.keyboard.tab"), (26, "key.keyboard.left.bracket"), (27, "key.keyboard.right.bracket"),
+    (28, "key.keyboard.enter"), (29, "key.keyboard.left.control"), (39, "key.keyboard.semicolon"),
+    (40, "key.keyboard.apostrophe"), (41, "key.keyboard.grave.accent"), (42, "key.keyboard.left.shift"),
+    (43, "key.keyboard.backslash"), (51, "key.keyboard.comma"), (52, "key.keyboard.period"),
+    (53, "key.keyboard.slash"), (54, "key.keyboard.right.shift"), (55, "key.keyboard.keypad.multiply"),
+    (56, "key.keyboard.left.alt"), (57, "key.keyboard.space"), (58, "key.keyboard.caps.lock"),
+    (87, "key.keyboard.f11"), (88, "key.keyboard.f12"), (157, "key.keyboard.right.control"),
+    (184, "key.keyboard.right.alt"), (200, "key.keyboard.up"), (203, "key.keyboard.left"),
+    (205, "key.keyboard.right"), (208, "key.keyboard.down"), (-100, "key.mouse.left"),
+    (-99, "key.mouse.right"), (-98, "key.mouse.middle"), (-97, "key.mouse.4"), (-96, "key.mouse.5"),
+  ]
+    .into_iter()
+    .map(|(code, name)| (code, name.to_string()))
+    .collect::<Vec<_>>();
+
+  for (first, keys) in [(16, "qwertyuiop"), (30, "asdfghjkl"), (44, "zxcvbnm")] {
+    codes.extend(keys.chars().zip(first..).map(|(key, code)| (code, format!("key.keyboard.{}", key))));
+  }
+
+  codes.extend((1..=9).map(|it| (it + 1, format!("key.keyboard.{}", it))));
+  codes.extend((1..=10).map(|it| (it + 58, format!("key.keyboard.f{}", it))));
+  codes
+}
+
+/// Rewrites an `options.txt` entry from whichever version the template was made with into what
+/// Minecraft `mc_version` expects. Key bindings switched from key codes to names in 1.13, language codes
+/// became lowercase in 1.11 and `fancyGraphics` became `graphicsMode` in 1.16
+fn translate(key: &str, value: &str, mc_version: &str) -> (String, String) {
+  let since = |version: &str| compare(mc_version, version) != Ordering::Less;
+
+  if key.starts_with("key_") {
+    let codes = key_codes();
+    let translated = match value.parse::<i32>() {
+      Ok(code) if since("1.13") => codes.iter().find(|it| it.0 == code).map(|it| it.1.clone()),
+      Err(_) if !since("1.13") => codes.iter().find(|it| it.1 == value).map(|it| it.0.to_string()),
+      _ => None,
+    };
+
+    return (key.to_string(), translated.unwrap_or_else(|| value.to_string()));
+  }
+
+  match key {
+    "lang" if since("1.11") => (key.to_string(), value.to_lowercase()),
+    "lang" => {
+      let value = match value.split_once('_') {
+        Some((language, region)) => format!("{}_{}", language, region.to_uppercase()),
+        None => value.to_string(),
+      };
+
+      (key.to_string(), value)
+    }
+    "fancyGraphics" if since("1.16") => {
+      (String::from("graphicsMode"), String::from(if value == "true" { "1" } else { "0" }))
+    }
+    "graphicsMode" if !since("1.16") => {
+      (String::from("fancyGraphics"), String::from(if value == "0" { "false" } else { "true" }))
+    }
+    _ => (key.to_string(), value.to_string()),
+  }
+}
+
+/// Merges `template` into `options` key by key. Keys matching one of `wins` take the template's value,
+/// the others only fill in what `options` doesn't have yet. Returns the keys that changed
+fn merge(options: &mut Vec<(String, String)>, template: Vec<(String, String)>, wins: &[String]) -> Vec<String> {
+  let mut changed = Vec::new();
+
+  for (key, value) in template {
+    if key == VERSION_KEY {
+      continue;
+    }
+
+    match options.iter_mut().find(|it| it.0 == key) {
+      Some(existing) => {
+        if existing.1 != value && wins.iter().any(|it| matches(it, &key)) {
+          existing.1 = value;
+          changed.push(key);
+        }
+      }
+      None => {
+        options.push((key.clone(), value));
+        changed.push(key);
+      }
+    }
+  }
+
+  changed
+}
+
+/// Merges the template files into `game_dir` of an instance for Minecraft `mc_version`, returns the keys
+/// that changed per file. Nothing is written when `dry_run` is set
+pub fn apply(
+  settings: &ApplicationSettings,
+  game_dir: &Path,
+  mc_version: &str,
+  dry_run: bool,
+) -> Result<Vec<(&'static str, Vec<String>)>> {
+  let template_dir = template_dir(settings);
+  let mut changes = Vec::new();
+
+  for (file, separator) in OPTIONS_FILES {
+    let template = match read_to_string(template_dir.join(file)) {
+      Ok(text) => parse(&text, separator),
+      Err(_) => continue,
+    };
+
+    let template = match file {
+      "options.txt" => template.into_iter().map(|(key, value)| translate(&key, &value, mc_version)).collect(),
+      _ => template,
+    };
+
+    let path = game_dir.join(file);
+    let mut options = read_to_string(&path).map(|it| parse(&it, separator)).unwrap_or_default();
+    let changed = merge(&mut options, template, &settings.options.keys);
+
+    if changed.is_empty() {
+      continue;
+    }
+
+    if !dry_run {
+      create_dir_all(game_dir)?;
+      write(&path, format(&options, separator))?;
+    }
+
+    changes.push((file, changed));
+  }
+
+  Ok(changes)
+}
+
+/// Game directories of every instance linked or detached by this application, as the target launcher sees them
+pub fn linked_instances(settings: &ApplicationSettings) -> Vec<(ModPack, PathBuf)> {
+  let mmc = settings.mmc_dir();
+
+  ModPack::list(settings.cf_dir(), settings.source, &mut None)
+    .into_iter()
+    .filter_map(|mp| {
+      let mmc_path = mp.mmc_path(&mmc)?;
+      let dir = game_dir(&mmc_path);
+      let owned = is_marked(&mmc_path, MarkerKind::Link)
+        || is_marked(&mmc_path, MarkerKind::Detached)
+        || mp.instance().map(|it| is_symlink_to(&dir, &it.game_dir)).unwrap_or(false);
+
+      owned.then_some((mp, dir))
+    })
+    .collect()
+}
+
+/// Copies the options files of `game_dir` into the template directory, replacing the template
+pub fn save_template(settings: &ApplicationSettings, game_dir: &Path) -> Result<usize> {
+  let template_dir = template_dir(settings);
+  let mut saved = 0;
+
+  create_dir_all(&template_dir)?;
+
+  for (file, _) in OPTIONS_FILES {
+    if game_dir.join(file).is_file() {
+      copy(game_dir.join(file), template_dir.join(file))?;
+      saved += 1;
+    }
+  }
+
+  Ok(saved)
+}
@@ -0,0 +1,331 @@
+use crate::inventory::{inventory, jar_path};
+use crate::jar::read_jar;
+use crate::link::Result;
+use crate::modpack::ModPack;
+use crate::source::ModLoader;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+  Markdown,
+  /// Single page with its styles inlined
+  Html,
+  Csv,
+}
+
+impl ReportFormat {
+  pub const NAMES: [&'static str; 3] = ["markdown", "html", "csv"];
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "markdown" | "md" => Some(ReportFormat::Markdown),
+      "html" => Some(ReportFormat::Html),
+      "csv" => Some(ReportFormat::Csv),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportMod {
+  pub name: String,
+  pub version: String,
+  /// CurseForge project page, empty for mods CurseForge doesn't know about
+  pub url: String,
+  pub file_name: String,
+  /// `client`, `server` or `both`, empty when the jar doesn't say
+  pub side: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+  pub name: String,
+  pub mc_version: String,
+  pub loader: String,
+  pub mods: Vec<ReportMod>,
+}
+
+fn loader_name(loader: &ModLoader) -> String {
+  match (loader.component(), loader.version()) {
+    (Some((name, _)), Some(version)) => format!("{} {}", name, version),
+    _ => String::from("Vanilla"),
+  }
+}
+
+impl Report {
+  /// Collects the mods of `mp`, versions and sides come from the jars themselves when they declare them
+  pub fn new(mp: &ModPack) -> Result<Report> {
+    let instance = mp.instance()?;
+    let mods_dir = instance.game_dir.join("mods");
+
+    let mods = inventory(mp)?
+      .into_iter()
+      .filter(|it| !it.missing)
+      .map(|entry| {
+        let jar_mod = match entry.mods.is_empty() {
+          true => read_jar(&jar_path(&mods_dir, &entry.file_name)).unwrap_or_default().into_iter().next(),
+          false => entry.mods.first().cloned(),
+        };
+
+        ReportMod {
+          version: jar_mod.as_ref()
+            .map(|it| it.version.clone())
+            .filter(|it| !it.is_empty() && !it.starts_with('$'))
+            .unwrap_or_default(),
+          url: entry.project_id
+            .map(|it| format!("https://www.curseforge.com/projects/{}", it))
+            .unwrap_or_default(),
+          side: jar_mod.and_then(|it| it.side).unwrap_or_default(),
+          name: entry.name,
+          file_name: entry.file_name,
+        }
+      })
+      .collect();
+
+    Ok(Report {
+      name: instance.name,
+      mc_version: instance.mc_version,
+      loader: loader_name(&instance.loader),
+      mods,
+    })
+  }
+
+  pub fn render(&self, format: ReportFormat) -> String {
+    match format {
+      ReportFormat::Markdown => self.render_markdown(),
+      ReportFormat::Html => self.render_html(),
+      ReportFormat::Csv => self.render_csv(),
+    }
+  }
+
+  fn render_markdown(&self) -> String {
+    let cell = |it: &str| it.replace('|', "\\|");
+    let rows = self.mods.iter()
+      .map(|it| {
+        let name = match it.url.is_empty() {
+          true => cell(&it.name),
+          false => format!("[{}]({})", cell(&it.name), it.url),
+        };
+
+        format!("| {} | {} | `{}` | {} |\n", name, cell(&it.version), it.file_name, it.side)
+      })
+      .collect::<String>();
+
+    format!(
+      "# {}\n\nMinecraft {}, {}, {} mods\n\n| Name | Version | File | Side |\n| --- | --- | --- | --- |\n{}",
+      self.name, self.mc_version, self.loader, self.mods.len(), rows
+    )
+  }
+
+  fn render_csv(&self) -> String {
+    let field = |it: &str| match it.contains([',', '"', '\n']) {
+      true => format!("\"{}\"", it.replace('"', "\"\"")),
+      false => it.to_string(),
+    };
+
+    // Every row repeats the instance, so the file stays a plain table that spreadsheets can filter
+    std::iter::once(String::from("name,version,url,file,side,minecraft,loader\n"))
+      .chain(self.mods.iter().map(|it| {
+        format!(
+          "{},{},{},{},{},{},{}\n",
+          field(&it.name), field(&it.version), field(&it.url), field(&it.file_name), field(&it.side),
+          field(&self.mc_version), field(&self.loader)
+        )
+      }))
+      .collect()
+  }
+
+  fn render_html(&self) -> String {
+    let rows = self.mods.iter()
+      .map(|it| {
+        let name = match it.url.is_empty() {
+          true => escape_html(&it.name),
+          false => format!("<a href=\"{}\">{}</a>", escape_html(&it.url), escape_html(&it.name)),
+        };
+
+        format!(
+          "      <tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+          name, escape_html(&it.version), escape_html(&it.file_name), escape_html(&it.side)
+        )
+      })
+      .collect::<String>();
+
+    substitute(HTML_TEMPLATE, &[
+      ("name", &escape_html(&self.name)),
+      ("minecraft", &escape_html(&self.mc_version)),
+      ("loader", &escape_html(&self.loader)),
+      ("count", &self.mods.len().to_string()),
+      ("rows", &rows),
+    ])
+  }
+
+  /// Renders a user supplied template. `{{name}}`, `{{minecraft}}`, `{{loader}}` and `{{count}}` describe the
+  /// instance, the part between `{{#mods}}` and `{{/mods}}` is repeated for every mod with `{{name}}`,
+  /// `{{version}}`, `{{url}}`, `{{file}}` and `{{side}}` filled in
+  pub fn render_template(&self, template: &str) -> String {
+    let (before, section, after) = match template.split_once("{{#mods}}") {
+      Some((before, rest)) => match rest.split_once("{{/mods}}") {
+        Some((section, after)) => (before, section, after),
+        None => (before, rest, ""),
+      },
+      None => (template, "", ""),
+    };
+
+    let count = self.mods.len().to_string();
+    let instance = |it: &str| {
+      substitute(it, &[
+        ("name", &self.name),
+        ("minecraft", &self.mc_version),
+        ("loader", &self.loader),
+        ("count", &count),
+      ])
+    };
+
+    let mods = self.mods.iter()
+      .map(|it| {
+        substitute(section, &[
+          ("name", &it.name),
+          ("version", &it.version),
+          ("url", &it.url),
+          ("file", &it.file_name),
+          ("side", &it.side),
+        ])
+      })
+      .collect::<String>();
+
+    format!("{}{}{}", instance(before), mods, instance(after))
+  }
+}
+
+/// Replaces `{{key}}` placeholders in `template` in a single pass, so values are never expanded themselves.
+/// Unknown placeholders are kept as they are
+fn substitute(template: &str, values: &[(&str, &str)]) -> String {
+  let mut out = String::with_capacity(template.len());
+  let mut rest = template;
+
+  while let Some(start) = rest.find("{{") {
+    out.push_str(&rest[..start]);
+    rest = &rest[start..];
+
+    let value = rest.find("}}")
+      .and_then(|end| values.iter().find(|(key, _)| *key == &rest[2..end]).map(|(_, value)| (end, value)));
+
+    match value {
+      Some((end, value)) => {
+        out.push_str(value);
+        rest = &rest[end + 2..];
+      }
+      None => {
+        out.push_str("{{");
+        rest = &rest[2..];
+      }
+    }
+  }
+
+  out.push_str(rest);
+  out
+}
+
+fn escape_html(it: &str) -> String {
+  it.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>{{name}}</title>
+  <style>
+    body { font-family: sans-serif; margin: 2em; color: #222; }
+    table { border-collapse: collapse; width: 100%; }
+    th, td { text-align: left; padding: 0.4em 0.8em; border-bottom: 1px solid #ddd; }
+    tr:nth-child(even) { background: #f6f6f6; }
+  </style>
+</head>
+<body>
+  <h1>{{name}}</h1>
+  <p>Minecraft {{minecraft}}, {{loader}}, {{count}} mods</p>
+  <table>
+    <thead>
+      <tr><th>Name</th><th>Version</th><th>File</th><th>Side</th></tr>
+    </thead>
+    <tbody>
+{{rows}}    </tbody>
+  </table>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn report() -> Report {
+    let jar = |name: &str, version: &str, url: &str, side: &str| ReportMod {
+      name: name.to_string(),
+      version: version.to_string(),
+      url: url.to_string(),
+      file_name: format!("{}.jar", name.to_lowercase().replace(' ', "-")),
+      side: side.to_string(),
+    };
+
+    Report {
+      name: String::from("Pack <1>"),
+      mc_version: String::from("1.20.1"),
+      loader: String::from("Forge 47.1.0"),
+      mods: vec![
+        jar("Create", "0.5.1", "https://www.curseforge.com/projects/328085", "both"),
+        jar("Oh, \"quotes\" | pipes", "1", "", ""),
+        jar("{{loader}} {{rows}}", "{{version}}", "", "client"),
+      ],
+    }
+  }
+
+  #[test]
+  fn markdown() {
+    let markdown = report().render(ReportFormat::Markdown);
+
+    assert!(markdown.starts_with("# Pack <1>\n\nMinecraft 1.20.1, Forge 47.1.0, 3 mods\n"));
+    assert!(markdown.contains("| [Create](https://www.curseforge.com/projects/328085) | 0.5.1 | `create.jar` | both |\n"));
+    assert!(markdown.contains("| Oh, \"quotes\" \\| pipes | 1 |"));
+  }
+
+  #[test]
+  fn csv_has_the_instance_on_every_row() {
+    let csv = report().render(ReportFormat::Csv);
+    let lines = csv.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines[0], "name,version,url,file,side,minecraft,loader");
+    assert_eq!(lines[1], "Create,0.5.1,https://www.curseforge.com/projects/328085,create.jar,both,1.20.1,Forge 47.1.0");
+    assert_eq!(lines[2], "\"Oh, \"\"quotes\"\" | pipes\",1,,\"oh,-\"\"quotes\"\"-|-pipes.jar\",,1.20.1,Forge 47.1.0");
+    assert_eq!(lines.len(), 4);
+  }
+
+  #[test]
+  fn html_escapes_and_never_expands_values() {
+    let html = report().render(ReportFormat::Html);
+
+    assert!(html.contains("<title>Pack &lt;1&gt;</title>"));
+    assert!(html.contains("<p>Minecraft 1.20.1, Forge 47.1.0, 3 mods</p>"));
+    assert!(html.contains("<td><a href=\"https://www.curseforge.com/projects/328085\">Create</a></td>"));
+    assert!(html.contains("<td>{{loader}} {{rows}}</td><td>{{version}}</td>"));
+    assert_eq!(html.matches("<tr><td>").count(), 3);
+  }
+
+  #[test]
+  fn templates_repeat_the_mods_section() {
+    let template = "{{name}} ({{count}}, {{unknown}})\n{{#mods}}- {{name}} {{version}} {{side}}\n{{/mods}}{{loader}}";
+    let rendered = report().render_template(template);
+
+    assert_eq!(rendered, concat!(
+      "Pack <1> (3, {{unknown}})\n",
+      "- Create 0.5.1 both\n",
+      "- Oh, \"quotes\" | pipes 1 \n",
+      "- {{loader}} {{rows}} {{version}} client\n",
+      "Forge 47.1.0",
+    ));
+    assert_eq!(substitute("{{a}} {{ {{a}}}} }}", &[("a", "{{a}}")]), "{{a}} {{ {{a}}}} }}");
+  }
+}
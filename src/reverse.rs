@@ -0,0 +1,238 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Formatter;
+use std::fs::{create_dir_all, File, remove_file};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::cfg::read_cfg;
+use crate::directories::{check_name, CurseForgeDirectory, Directory, MultiMCDirectory};
+use crate::launcher::game_dir;
+use crate::link::Result;
+use crate::marker::{is_marked, is_symlink_to, Marker, MarkerKind};
+use crate::source::{ModLoader, SourceInstance};
+
+/// A MultiMC instance that can be exposed inside CurseForge
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct MMCInstance {
+  pub dir: PathBuf,
+  pub name: String,
+}
+
+impl std::fmt::Display for MMCInstance {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.name)
+  }
+}
+
+#[derive(Deserialize)]
+struct MMCPack {
+  components: Vec<MMCComponent>,
+}
+
+#[derive(Deserialize)]
+struct MMCComponent {
+  uid: String,
+  #[serde(default)] version: String,
+}
+
+impl MMCInstance {
+  /// Every instance in `mmc` that wasn't itself linked from another launcher
+  pub fn list(mmc: &MultiMCDirectory) -> Vec<MMCInstance> {
+    let mut instances = mmc.path()
+      .read_dir()
+      .map(|it| it.flatten()
+        .map(|it| it.path())
+        .filter(|it| !is_marked(it, MarkerKind::Link))
+        .filter_map(|dir| {
+          let cfg = read_cfg(dir.join("instance.cfg")).ok()?;
+          let name = cfg.get("name").cloned().unwrap_or_default();
+
+          Some(MMCInstance { dir, name })
+        })
+        .collect::<Vec<_>>()
+      )
+      .unwrap_or_default();
+
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    instances
+  }
+
+  /// Reads the instance from `instance.cfg` and `mmc-pack.json`
+  pub fn read(&self) -> Result<SourceInstance> {
+    let pack = serde_json::from_reader::<_, MMCPack>(File::open(self.dir.join("mmc-pack.json"))?)?;
+    let mc_version = pack.components.iter()
+      .find(|it| it.uid == "net.minecraft")
+      .map(|it| it.version.clone())
+      .ok_or("mmc-pack.json has no Minecraft component")?;
+
+    let loader = pack.components.into_iter()
+      .find_map(|it| ModLoader::from_component(&it.uid, it.version))
+      .unwrap_or(ModLoader::Vanilla);
+
+    Ok(SourceInstance {
+      name: self.name.clone(),
+      mc_version,
+      loader,
+      game_dir: game_dir(&self.dir),
+      icon: None,
+      managed: None,
+    })
+  }
+
+  /// Where the instance shows up in CurseForge
+  pub fn cf_path(&self, cf: &CurseForgeDirectory) -> PathBuf {
+    cf.path().join(&self.name)
+  }
+
+  pub fn is_reverse_linked(&self, cf: &CurseForgeDirectory) -> bool {
+    let game_dir = game_dir(&self.dir);
+
+    check_name(&self.name).is_ok()
+      && is_marked(&game_dir, MarkerKind::ReverseLink)
+      && is_symlink_to(&self.cf_path(cf), &game_dir)
+  }
+}
+
+/// Random-enough GUID for `minecraftinstance.json`, CurseForge only needs it to be unique
+fn guid(seed: &str) -> String {
+  let mut hasher = DefaultHasher::new();
+
+  seed.hash(&mut hasher);
+  SystemTime::now().hash(&mut hasher);
+
+  let a = hasher.finish();
+
+  a.hash(&mut hasher);
+
+  let b = hasher.finish();
+
+  format!(
+    "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+    a >> 32, (a >> 16) & 0xffff, a & 0xfff, b >> 48, b & 0xffff_ffff_ffff
+  )
+}
+
+/// Minimal `minecraftinstance.json` CurseForge accepts as a custom profile
+pub fn gen_cf_instance_json(instance: &SourceInstance, install_path: &Path) -> serde_json::Value {
+  let mc_version = &instance.mc_version;
+  let version = instance.loader.version().unwrap_or_default();
+  let (name, kind) = match &instance.loader {
+    ModLoader::Forge(_) => (format!("forge-{}", version), 1),
+    ModLoader::Fabric(_) => (format!("fabric-{}-{}", version, mc_version), 4),
+    ModLoader::Quilt(_) => (format!("quilt-{}-{}", version, mc_version), 5),
+    ModLoader::NeoForge(_) => (format!("neoforge-{}", version), 6),
+    ModLoader::Vanilla => (String::new(), 0),
+  };
+
+  let base_mod_loader = match instance.loader {
+    ModLoader::Vanilla => serde_json::Value::Null,
+    _ => serde_json::json!({
+      "name": name,
+      "type": kind,
+      "forgeVersion": version,
+      "minecraftVersion": mc_version
+    }),
+  };
+
+  // CurseForge runs in Wine on Linux, it can't resolve a host path
+  let install_path = crate::wine::windows_path(install_path)
+    .map(|it| format!("{}\\", it))
+    .unwrap_or_else(|| format!("{}{}", install_path.display(), std::path::MAIN_SEPARATOR));
+
+  serde_json::json!({
+    "baseModLoader": base_mod_loader,
+    "gameTypeID": 432,
+    "gameVersion": mc_version,
+    "guid": guid(&instance.name),
+    "installPath": install_path,
+    "installedAddons": [],
+    "isVanilla": instance.loader == ModLoader::Vanilla,
+    "isUnlocked": true,
+    "lastPlayed": "0001-01-01T00:00:00",
+    "name": instance.name,
+    "playedCount": 0,
+    "projectID": 0,
+    "fileID": 0
+  })
+}
+
+/// Symlinks the instance's game directory into the CurseForge instances
+/// and writes a `minecraftinstance.json` for it
+pub fn reverse_link(mmc_instance: &MMCInstance, cf: &CurseForgeDirectory) -> Result<()> {
+  check_name(&mmc_instance.name)?;
+
+  let instance = mmc_instance.read()?;
+  let cf_path = mmc_instance.cf_path(cf);
+
+  if cf_path.symlink_metadata().is_ok() {
+    return Err(Box::from("A CurseForge instance with that name already exists"));
+  }
+
+  if instance.game_dir.join("minecraftinstance.json").exists() {
+    return Err(Box::from("This instance already has a minecraftinstance.json"));
+  }
+
+  create_dir_all(&instance.game_dir)?;
+
+  let json = gen_cf_instance_json(&instance, &cf_path);
+
+  serde_json::to_writer_pretty(File::create(instance.game_dir.join("minecraftinstance.json"))?, &json)?;
+  Marker::new(MarkerKind::ReverseLink, &instance.game_dir).write(&instance.game_dir)?;
+
+  if symlink::symlink_dir(&instance.game_dir, &cf_path).is_err() {
+    remove_file(instance.game_dir.join("minecraftinstance.json"))?;
+    Marker::remove(&instance.game_dir)?;
+
+    return Err(Box::from("No permission to create symlink (Needs admin perms)"));
+  }
+
+  Ok(())
+}
+
+/// Removes what [`reverse_link`] created, the MultiMC instance itself is left untouched
+pub fn reverse_unlink(mmc_instance: &MMCInstance, cf: &CurseForgeDirectory) -> Result<()> {
+  check_name(&mmc_instance.name)?;
+
+  if !mmc_instance.is_reverse_linked(cf) {
+    return Err(Box::from("This instance wasn't linked to CurseForge by this application"));
+  }
+
+  let game_dir = game_dir(&mmc_instance.dir);
+
+  symlink::remove_symlink_dir(mmc_instance.cf_path(cf))?;
+  remove_file(game_dir.join("minecraftinstance.json"))?;
+  Marker::remove(&game_dir)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn names_outside_the_curseforge_dir_are_rejected() {
+    let dir = std::env::temp_dir().join(format!("reverse-{}-names", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir_all(dir.join("cf")).unwrap();
+    create_dir_all(dir.join("mmc/Pack")).unwrap();
+    std::fs::write(
+      dir.join("mmc/Pack/mmc-pack.json"),
+      r#"{"components": [{"uid": "net.minecraft", "version": "1.20.1"}]}"#,
+    ).unwrap();
+
+    let cf = CurseForgeDirectory::new(dir.join("cf"));
+    let instance = MMCInstance { dir: dir.join("mmc/Pack"), name: "../escaped".into() };
+
+    assert!(instance.read().is_ok());
+
+    assert!(reverse_link(&instance, &cf).is_err());
+    assert!(reverse_unlink(&instance, &cf).is_err());
+    assert!(!dir.join("escaped").exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,228 @@
+use std::fs::{File, rename};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::nbt::Tag;
+
+pub const SERVERS_FILE: &str = "servers.dat";
+
+/// A server of the central list, stored in the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Server {
+  pub name: String,
+  /// Address as typed into Minecraft, the port can be left out
+  pub ip: String,
+}
+
+impl Server {
+  fn to_tag(&self) -> Tag {
+    Tag::Compound(vec![
+      (String::from("name"), Tag::String(self.name.clone())),
+      (String::from("ip"), Tag::String(self.ip.clone())),
+    ])
+  }
+}
+
+/// `ip` in a form that compares equal for the same server, `Example.com:25565` and `example.com` match
+fn normalize(ip: &str) -> String {
+  let ip = ip.trim().to_lowercase();
+
+  match ip.strip_suffix(":25565") {
+    Some(host) => host.to_string(),
+    None => ip,
+  }
+}
+
+fn ip(server: &Tag) -> String {
+  normalize(server.get("ip").and_then(Tag::as_str).unwrap_or_default())
+}
+
+/// Server entries of a `servers.dat`, empty when there's none yet
+fn read_servers(path: &Path) -> Result<Vec<Tag>> {
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+
+  let (_, root) = crate::nbt::read(&mut BufReader::new(File::open(path)?))?;
+
+  match root.get("servers") {
+    Some(Tag::List(_, servers)) => Ok(servers.clone()),
+    Some(_) => Err(Box::from("servers.dat has no list of servers")),
+    None => Ok(Vec::new()),
+  }
+}
+
+fn write_servers(path: &Path, servers: Vec<Tag>) -> Result<()> {
+  let root = Tag::Compound(vec![(String::from("servers"), Tag::List(10, servers))]);
+  let temp = path.with_file_name(format!("{}.tmp", SERVERS_FILE));
+
+  let mut writer = BufWriter::new(File::create(&temp)?);
+
+  crate::nbt::write(&mut writer, "", &root)?;
+  writer.flush()?;
+  rename(&temp, path)?;
+
+  Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergeReport {
+  /// Names of the servers that were missing
+  pub added: Vec<String>,
+  /// Whether the list changed at all, pinning can reorder it without adding anything
+  pub changed: bool,
+}
+
+/// Merges `servers` into the server list of `game_dir`. Servers are matched by address, the instance's own
+/// entries are kept as they are. With `pin` the central servers are moved to the top in their order,
+/// otherwise missing ones are added at the bottom. Nothing is written when `dry_run` is set
+pub fn merge(game_dir: &Path, servers: &[Server], pin: bool, dry_run: bool) -> Result<MergeReport> {
+  let path = game_dir.join(SERVERS_FILE);
+  let original = read_servers(&path)?;
+  let mut existing = original.clone();
+  let mut pinned = Vec::new();
+  let mut added = Vec::new();
+
+  for server in servers {
+    if pinned.iter().any(|it| ip(it) == normalize(&server.ip)) {
+      continue;
+    }
+
+    let entry = match existing.iter().position(|it| ip(it) == normalize(&server.ip)) {
+      Some(index) if pin => existing.remove(index),
+      Some(_) => continue,
+      None => {
+        added.push(server.name.clone());
+        server.to_tag()
+      }
+    };
+
+    pinned.push(entry);
+  }
+
+  let merged = match pin {
+    true => pinned.into_iter().chain(existing).collect::<Vec<_>>(),
+    false => existing.into_iter().chain(pinned).collect(),
+  };
+
+  let changed = original != merged;
+
+  if !dry_run && changed {
+    write_servers(&path, merged)?;
+  }
+
+  Ok(MergeReport { added, changed })
+}
+
+/// Merges the configured servers into `game_dir`, used when linking
+pub fn apply(settings: &ApplicationSettings, game_dir: &Path) -> Result<MergeReport> {
+  if settings.servers.list.is_empty() {
+    return Ok(MergeReport::default());
+  }
+
+  merge(game_dir, &settings.servers.list, settings.servers.pin, false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn game_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("servers-{}-{}", std::process::id(), name));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn server(name: &str, ip: &str) -> Server {
+    Server { name: name.to_string(), ip: ip.to_string() }
+  }
+
+  fn names(dir: &Path) -> Vec<String> {
+    read_servers(&dir.join(SERVERS_FILE)).unwrap()
+      .iter()
+      .map(|it| it.get("name").and_then(Tag::as_str).unwrap_or_default().to_string())
+      .collect()
+  }
+
+  /// `servers.dat` the way Minecraft writes it, the name of the first server is `Home 🏠` with the emoji as a
+  /// surrogate pair in modified UTF-8
+  fn java_servers_dat() -> Vec<u8> {
+    let mut bytes = vec![10, 0, 0, 9, 0, 7];
+    bytes.extend(b"servers");
+    bytes.extend([10, 0, 0, 0, 2]);
+
+    for (name, ip) in [(&b"Home \xed\xa0\xbc\xed\xbf\xa0"[..], &b"home.example"[..]), (b"Other", b"Example.com:25565")] {
+      bytes.extend([8, 0, 4]);
+      bytes.extend(b"name");
+      bytes.extend((name.len() as u16).to_be_bytes());
+      bytes.extend(name);
+      bytes.extend([8, 0, 2]);
+      bytes.extend(b"ip");
+      bytes.extend((ip.len() as u16).to_be_bytes());
+      bytes.extend(ip);
+      bytes.push(0);
+    }
+
+    bytes.push(0);
+    bytes
+  }
+
+  #[test]
+  fn keeps_existing_servers_and_adds_missing_ones() {
+    let dir = game_dir("merge");
+    std::fs::write(dir.join(SERVERS_FILE), java_servers_dat()).unwrap();
+
+    let report = merge(&dir, &[server("Example", "example.com"), server("New \u{1F680}", "new.example")], false, false)
+      .unwrap();
+
+    assert_eq!(report.added, ["New \u{1F680}"]);
+    assert!(report.changed);
+    assert_eq!(names(&dir), ["Home \u{1F3E0}", "Other", "New \u{1F680}"]);
+
+    let written = std::fs::read(dir.join(SERVERS_FILE)).unwrap();
+    assert!(written.windows(6).any(|it| it == b"\xed\xa0\xbc\xed\xbf\xa0"));
+    assert!(!written.contains(&0xf0));
+
+    let again = merge(&dir, &[server("Example", "example.com"), server("New", "NEW.example:25565")], false, false)
+      .unwrap();
+
+    assert!(again.added.is_empty());
+    assert!(!again.changed);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn pinning_moves_central_servers_to_the_top() {
+    let dir = game_dir("pin");
+    std::fs::write(dir.join(SERVERS_FILE), java_servers_dat()).unwrap();
+
+    let report = merge(&dir, &[server("New", "new.example"), server("Example", "example.com")], true, false).unwrap();
+
+    assert_eq!(report.added, ["New"]);
+    assert_eq!(names(&dir), ["New", "Other", "Home \u{1F3E0}"]);
+
+    let again = merge(&dir, &[server("New", "new.example"), server("Example", "example.com")], true, false).unwrap();
+
+    assert!(!again.changed);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn dry_run_writes_nothing() {
+    let dir = game_dir("dry-run");
+    let report = merge(&dir, &[server("Example", "example.com"), server("Example", "example.com")], false, true).unwrap();
+
+    assert_eq!(report.added, ["Example"]);
+    assert!(!dir.join(SERVERS_FILE).exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
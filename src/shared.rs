@@ -0,0 +1,146 @@
+use std::fs::{copy, create_dir_all, rename};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::detach::same_contents;
+use crate::link::Result;
+use crate::marker::is_symlink_to;
+use crate::misc::ApplicationSettings;
+use crate::state::data_dir;
+
+/// What linking does with a folder of the instance that's configured to be shared
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharePolicy {
+  /// The instance keeps its own folder
+  #[default] Off,
+  /// The instance's files are copied into the shared folder before it replaces the instance's folder
+  Merge,
+  /// The shared folder replaces the instance's folder as it is
+  Replace,
+}
+
+/// Where the folder called `folder` shared between all linked instances is
+pub fn shared_dir(settings: &ApplicationSettings, folder: &str) -> PathBuf {
+  settings.shared.directory.as_ref()
+    .map(PathBuf::from)
+    .unwrap_or_else(|| data_dir().join("shared"))
+    .join(folder)
+}
+
+/// Where the instance's own folder is kept while it's shared
+fn set_aside(game_dir: &Path, folder: &str) -> PathBuf {
+  game_dir.join(format!(".{}.unshared", folder))
+}
+
+/// `path` with ` (name)` added before its extension, `pack.zip` becomes `pack (Instance).zip`
+fn renamed(path: &Path, name: &str) -> PathBuf {
+  let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+  let file_name = match path.extension() {
+    Some(extension) => format!("{} ({}).{}", stem, name, extension.to_string_lossy()),
+    None => format!("{} ({})", stem, name),
+  };
+
+  path.with_file_name(file_name)
+}
+
+/// Copies the files of `from` into `to`. Files that exist in both with different contents are copied with
+/// the instance name added to theirs, those are returned
+fn merge(from: &Path, to: &Path, name: &str) -> Result<Vec<PathBuf>> {
+  let mut conflicts = Vec::new();
+
+  for entry in WalkDir::new(from).min_depth(1) {
+    let entry = entry?;
+
+    if !entry.file_type().is_file() {
+      continue;
+    }
+
+    let mut dest = to.join(entry.path().strip_prefix(from)?);
+
+    if dest.exists() {
+      if same_contents(entry.path(), &dest)? {
+        continue;
+      }
+
+      dest = renamed(&dest, name);
+      conflicts.push(dest.clone());
+    }
+
+    if let Some(parent) = dest.parent() {
+      create_dir_all(parent)?;
+    }
+
+    copy(entry.path(), &dest)?;
+  }
+
+  Ok(conflicts)
+}
+
+/// Replaces the folders of `game_dir` that have a share policy with symlinks to the shared folders. The
+/// instance's own folders are set aside so [`unshare`] can bring them back. Returns the files that were
+/// renamed because a different file of the same name was already shared
+pub fn share(settings: &ApplicationSettings, name: &str, game_dir: &Path) -> Result<Vec<PathBuf>> {
+  let mut conflicts = Vec::new();
+
+  for (folder, policy) in settings.shared.policies() {
+    if policy == SharePolicy::Off {
+      continue;
+    }
+
+    let shared = shared_dir(settings, folder);
+    let path = game_dir.join(folder);
+    let aside = set_aside(game_dir, folder);
+
+    if is_symlink_to(&path, &shared) {
+      continue;
+    }
+
+    create_dir_all(&shared)?;
+
+    if path.symlink_metadata().is_ok() {
+      if aside.exists() {
+        return Err(format!("{} was already set aside once, remove or restore it first", aside.display()).into());
+      }
+
+      rename(&path, &aside)?;
+
+      if policy == SharePolicy::Merge {
+        conflicts.extend(merge(&aside, &shared, name)?);
+      }
+    }
+
+    if let Err(err) = symlink::symlink_dir(&shared, &path) {
+      if aside.exists() {
+        rename(&aside, &path)?;
+      }
+
+      return Err(err.into());
+    }
+  }
+
+  Ok(conflicts)
+}
+
+/// Removes the symlinks made by [`share`] and puts the instance's own folders back. Files added to the shared
+/// folders stay there
+pub fn unshare(settings: &ApplicationSettings, game_dir: &Path) -> Result<()> {
+  for (folder, _) in settings.shared.policies() {
+    let path = game_dir.join(folder);
+    let aside = set_aside(game_dir, folder);
+
+    if !is_symlink_to(&path, &shared_dir(settings, folder)) {
+      continue;
+    }
+
+    symlink::remove_symlink_dir(&path)?;
+
+    if aside.exists() {
+      rename(&aside, &path)?;
+    }
+  }
+
+  Ok(())
+}
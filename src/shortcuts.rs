@@ -0,0 +1,126 @@
+use std::fs::{create_dir_all, remove_file, write};
+use std::path::{Path, PathBuf};
+
+use crate::export::instance_icon;
+use crate::launch::find_executable;
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::source::SourceInstance;
+use crate::state::dir_name;
+
+/// Desktop entries this application writes start with this, so only those are ever removed
+const PREFIX: &str = "curseforge_to_multimc-";
+
+/// `~/.local/share/applications`, or wherever `XDG_DATA_HOME` points
+pub fn applications_dir() -> PathBuf {
+  directories::BaseDirs::new()
+    .map(|it| it.data_dir().join("applications"))
+    .unwrap_or_default()
+}
+
+fn entry_path(id: &str) -> PathBuf {
+  applications_dir().join(format!("{}{}.desktop", PREFIX, dir_name(id)))
+}
+
+/// Quotes an `Exec` argument the way the desktop entry spec wants it
+fn exec_arg(arg: &str) -> String {
+  const RESERVED: &str = " \t\n\"'\\><~|&;$*?#()`";
+
+  let arg = match arg.contains(|it| RESERVED.contains(it)) {
+    true => {
+      let escaped = arg.chars()
+        .flat_map(|it| match it {
+          '"' | '`' | '$' | '\\' => vec!['\\', it],
+          _ => vec![it],
+        })
+        .collect::<String>();
+
+      format!("\"{}\"", escaped)
+    }
+    false => arg.to_string(),
+  };
+
+  // Field codes start with %, and backslashes are escaped once more as the value is a string
+  arg.replace('%', "%%").replace('\\', "\\\\")
+}
+
+/// Single line value of a desktop entry key
+fn value(it: &str) -> String {
+  it.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// Writes a desktop entry that launches the instance at `mmc_path`, returns where it was written. Desktop entries
+/// only mean something on Linux, `None` everywhere else
+pub fn create(settings: &ApplicationSettings, instance: &SourceInstance, mmc_path: &Path) -> Result<Option<PathBuf>> {
+  if !cfg!(target_os = "linux") {
+    return Ok(None);
+  }
+
+  let id = mmc_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+  let executable = find_executable(settings)
+    .ok_or_else(|| format!("No {} executable found for the desktop entry, set launcher_executable", settings.target))?;
+  let icon = instance_icon(settings, &settings.mmc_dir(), mmc_path);
+
+  let mut entry = String::from("[Desktop Entry]\nType=Application\n");
+
+  entry.push_str(&format!("Name={}\n", value(&instance.name)));
+  entry.push_str(&format!("Comment=Minecraft {} in {}\n", value(&instance.mc_version), settings.target));
+  entry.push_str(&format!("Exec={} -l {}\n", exec_arg(&executable.to_string_lossy()), exec_arg(&id)));
+
+  if let Some(icon) = icon {
+    entry.push_str(&format!("Icon={}\n", value(&icon.to_string_lossy())));
+  }
+
+  entry.push_str("Terminal=false\nCategories=Game;\n");
+
+  let path = entry_path(&id);
+
+  create_dir_all(applications_dir())?;
+  write(&path, entry)?;
+
+  Ok(Some(path))
+}
+
+/// Removes the desktop entry of the instance with the directory name `id`, if there is one
+pub fn remove(id: &str) -> Result<()> {
+  let path = entry_path(id);
+
+  if path.exists() {
+    remove_file(path)?;
+  }
+
+  Ok(())
+}
+
+/// Replaces every desktop entry this application wrote with one for each linked instance, returns how many were
+/// written
+pub fn regenerate(settings: &ApplicationSettings) -> Result<usize> {
+  if !cfg!(target_os = "linux") {
+    return Err(Box::from("Desktop entries are only used on Linux"));
+  }
+
+  let ours = applications_dir()
+    .read_dir()
+    .map(|it| it.flatten()
+      .map(|it| it.path())
+      .filter(|it| it.file_name().map(|it| it.to_string_lossy().starts_with(PREFIX)).unwrap_or(false))
+      .collect::<Vec<_>>()
+    )
+    .unwrap_or_default();
+
+  for path in ours {
+    remove_file(path)?;
+  }
+
+  let mmc = settings.mmc_dir();
+  let mut written = 0;
+
+  for (mp, _) in crate::options::linked_instances(settings) {
+    if let Some(mmc_path) = mp.mmc_path(&mmc) {
+      create(settings, &mp.instance()?, &mmc_path)?;
+      written += 1;
+    }
+  }
+
+  Ok(written)
+}
@@ -0,0 +1,393 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{copy, create_dir_all, remove_file, rename};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use walkdir::WalkDir;
+
+use crate::export::relative_path;
+use crate::hash::sha1;
+use crate::link::Result;
+use crate::misc::ApplicationSettings;
+use crate::modpack::ModPack;
+use crate::state::{data_dir, InstanceState, snapshots_dir};
+
+/// Directories of the game directory a snapshot saves, along with the source launcher's instance file
+pub const SNAPSHOT_DIRS: [&str; 2] = ["mods", "config"];
+
+/// Files stored by their sha1, so unchanged mods take no extra space across snapshots
+pub struct Store {
+  pub dir: PathBuf,
+}
+
+impl Default for Store {
+  fn default() -> Self {
+    Self { dir: data_dir().join("store") }
+  }
+}
+
+impl Store {
+  /// Where the file with the sha1 `hash` is kept. Hashes come from snapshot files anyone could have edited,
+  /// so anything that isn't 40 hex digits is rejected
+  pub fn object(&self, hash: &str) -> Result<PathBuf> {
+    if hash.len() != 40 || !hash.bytes().all(|it| it.is_ascii_hexdigit()) {
+      return Err(format!("\"{}\" isn't a sha1 hash", hash).into());
+    }
+
+    Ok(self.dir.join(&hash[..2]).join(hash))
+  }
+
+  /// Copies `path` into the store unless it's there already, returns its hash
+  pub fn put(&self, path: &Path) -> Result<String> {
+    let hash = sha1(path)?;
+    let object = self.object(&hash)?;
+
+    if !object.is_file() {
+      let partial = object.with_extension("partial");
+
+      create_dir_all(object.parent().unwrap_or(&self.dir))?;
+      copy(path, &partial)?;
+      rename(&partial, &object)?;
+    }
+
+    Ok(hash)
+  }
+}
+
+/// Files a snapshot of `game_dir` covers, relative to it with `/` separators
+fn snapshot_files(game_dir: &Path, instance_file: &str) -> Result<Vec<String>> {
+  let mut files = Vec::new();
+
+  if game_dir.join(instance_file).is_file() {
+    files.push(instance_file.to_string());
+  }
+
+  for dir in SNAPSHOT_DIRS {
+    for entry in WalkDir::new(game_dir.join(dir)).follow_links(true).sort_by_file_name() {
+      let entry = match entry {
+        Ok(entry) if entry.file_type().is_file() => entry,
+        _ => continue,
+      };
+
+      files.push(relative_path(game_dir, entry.path())?);
+    }
+  }
+
+  Ok(files)
+}
+
+/// What `take` gives back, `None` when nothing changed since the last snapshot
+pub type Taken = Result<Option<Snapshot>>;
+
+/// A snapshot saved on disk
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+  pub path: PathBuf,
+  pub state: InstanceState,
+}
+
+impl Snapshot {
+  /// File name without extension, what `rollback` takes
+  pub fn id(&self) -> String {
+    self.path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+  }
+}
+
+/// `secs` since the unix epoch as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_time(secs: u64) -> String {
+  // Days to civil date, from Howard Hinnant's date algorithms
+  let days = (secs / 86400) as i64 + 719468;
+  let era = days.div_euclid(146097);
+  let doe = days - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  let time = secs % 86400;
+
+  format!(
+    "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+    year, month, day, time / 3600, time % 3600 / 60, time % 60
+  )
+}
+
+/// Snapshots of the instance called `name`, oldest first
+pub fn list(name: &str) -> Vec<Snapshot> {
+  let mut snapshots = snapshots_dir(name)
+    .read_dir()
+    .map(|it| it.flatten()
+      .map(|it| it.path())
+      .filter(|it| it.extension().map(|it| it == "json").unwrap_or(false))
+      .filter_map(|path| Some(Snapshot { state: InstanceState::read(&path).ok()?, path }))
+      .collect::<Vec<_>>()
+    )
+    .unwrap_or_default();
+
+  snapshots.sort_by_key(|it| (it.state.created, it.id()));
+  snapshots
+}
+
+/// Saves `mods`, `config` and the instance file of `mp` into the store. Returns `None` when nothing
+/// changed since the last snapshot, unless `force` is set
+pub fn take(mp: &ModPack, store: &Store, output: Option<&Path>, force: bool) -> Taken {
+  let mut state = InstanceState::capture(mp)?;
+  let game_dir = mp.instance()?.game_dir;
+
+  for file in snapshot_files(&game_dir, mp.source.launcher().instance_file())? {
+    let hash = store.put(&game_dir.join(&file))?;
+    state.files.insert(file, hash);
+  }
+
+  if !force && list(&state.name).last().map(|it| it.state.files == state.files).unwrap_or(false) {
+    return Ok(None);
+  }
+
+  let path = match output {
+    Some(output) => output.to_path_buf(),
+    None => {
+      let dir = snapshots_dir(&state.name);
+      let taken = |suffix: u32| dir.join(format!("{}-{}.json", state.created, suffix));
+
+      (0..).map(taken).find(|it| !it.exists()).unwrap_or_default()
+    }
+  };
+
+  state.write(&path)?;
+
+  Ok(Some(Snapshot { path, state }))
+}
+
+/// Finds a snapshot of `name` by its ID or path, the latest one when `query` is `None`
+pub fn find(name: &str, query: Option<&str>) -> Result<Snapshot> {
+  if let Some(path) = query.map(Path::new).filter(|it| it.is_file()) {
+    return Ok(Snapshot { path: path.to_path_buf(), state: InstanceState::read(path)? });
+  }
+
+  let snapshots = list(name);
+  let snapshot = match query {
+    Some(id) => snapshots.into_iter().find(|it| it.id() == id),
+    None => snapshots.into_iter().last(),
+  };
+
+  snapshot.ok_or_else(|| format!("No snapshot of \"{}\" found", name).into())
+}
+
+/// Restores `snapshot` into `mp`. Files in the snapshotted directories that the snapshot doesn't have are
+/// removed, the current state is snapshotted first so the rollback itself can be undone. Returns the number
+/// of files restored and removed
+pub fn rollback(mp: &ModPack, store: &Store, snapshot: &Snapshot) -> Result<(usize, usize)> {
+  if snapshot.state.files.is_empty() {
+    return Err(Box::from("This snapshot only has hashes to compare against, it can't be restored"));
+  }
+
+  // Paths have to stay inside the game directory, the snapshot file may come from anywhere
+  for (file, hash) in &snapshot.state.files {
+    if file.is_empty() || !Path::new(file).components().all(|it| matches!(it, Component::Normal(_))) {
+      return Err(format!("\"{}\" in this snapshot points outside the game directory", file).into());
+    }
+
+    store.object(hash)?;
+  }
+
+  let missing = snapshot.state.files.values()
+    .filter(|it| store.object(it).map(|it| !it.is_file()).unwrap_or(true))
+    .count();
+
+  if missing > 0 {
+    return Err(format!("{} files of this snapshot are missing from the store", missing).into());
+  }
+
+  take(mp, store, None, false)?;
+
+  let game_dir = mp.instance()?.game_dir;
+  let current = snapshot_files(&game_dir, mp.source.launcher().instance_file())?
+    .into_iter()
+    .map(|it| {
+      let hash = sha1(&game_dir.join(&it)).unwrap_or_default();
+      (it, hash)
+    })
+    .collect::<HashMap<_, _>>();
+
+  let mut removed = 0;
+  let mut restored = 0;
+
+  for file in current.keys().filter(|it| !snapshot.state.files.contains_key(*it)) {
+    remove_file(game_dir.join(file))?;
+    removed += 1;
+  }
+
+  for (file, hash) in &snapshot.state.files {
+    if current.get(file) == Some(hash) {
+      continue;
+    }
+
+    let path = game_dir.join(file);
+
+    if let Some(parent) = path.parent() {
+      create_dir_all(parent)?;
+    }
+
+    // Removed rather than overwritten, it may be a hardlink shared with other instances
+    if path.exists() {
+      remove_file(&path)?;
+    }
+
+    copy(store.object(hash)?, &path)?;
+    restored += 1;
+  }
+
+  Ok((restored, removed))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  path.metadata().and_then(|it| it.modified()).ok()
+}
+
+/// Polls the instance files of every linked instance and calls `on_change` when one changes, snapshots
+/// are taken before that when `snapshot` is set. Runs until the process is stopped
+pub fn watch(
+  settings: &ApplicationSettings,
+  interval: Duration,
+  snapshot: bool,
+  on_change: &mut dyn FnMut(&ModPack, Option<Taken>),
+) {
+  let store = Store::default();
+  let mut seen = BTreeMap::<PathBuf, Option<SystemTime>>::new();
+
+  loop {
+    let linked = ModPack::list(settings.cf_dir(), settings.source, &mut None)
+      .into_iter()
+      .filter(|it| it.is_linked(&settings.mmc_dir()));
+
+    for mp in linked {
+      let file = mp.path().join(mp.source.launcher().instance_file());
+      let time = modified(&file);
+      let previous = seen.insert(file, time);
+
+      // The first pass only records a baseline, so the next change has something to roll back to
+      if previous.is_none() {
+        if snapshot {
+          let _ = take(&mp, &store, None, false);
+        }
+
+        continue;
+      }
+
+      if previous != Some(time) {
+        on_change(&mp, snapshot.then(|| take(&mp, &store, None, false)));
+      }
+    }
+
+    std::thread::sleep(interval);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::source::Source;
+
+  use super::*;
+
+  /// Modrinth App profile with a couple of mods and configs, and a store next to it
+  fn instance(name: &str) -> (PathBuf, ModPack, Store) {
+    let dir = std::env::temp_dir().join(format!("snapshot-{}-{}", std::process::id(), name));
+    let profile = dir.join("profile");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    create_dir_all(profile.join("mods")).unwrap();
+    create_dir_all(profile.join("config/sub")).unwrap();
+    std::fs::write(profile.join("mods/a.jar"), "a").unwrap();
+    std::fs::write(profile.join("config/sub/b.toml"), "b").unwrap();
+    std::fs::write(profile.join("profile.json"), serde_json::json!({
+      "metadata": {
+        "name": format!("snapshot-test-{}-{}", std::process::id(), name),
+        "game_version": "1.20.1",
+        "loader": "vanilla"
+      }
+    }).to_string()).unwrap();
+
+    let mp = ModPack::new(profile, Source::Modrinth);
+    let store = Store { dir: dir.join("store") };
+
+    (dir, mp, store)
+  }
+
+  fn finish(dir: PathBuf, mp: &ModPack) {
+    let _ = std::fs::remove_dir_all(snapshots_dir(&mp.instance().unwrap().name));
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn objects_need_a_sha1() {
+    let store = Store { dir: PathBuf::from("store") };
+
+    assert_eq!(
+      store.object("0123456789abcdef0123456789ABCDEF01234567").unwrap(),
+      Path::new("store/01/0123456789abcdef0123456789ABCDEF01234567"),
+    );
+
+    let short = "0123456789abcdef0123456789abcdef0123456";
+    let multibyte = "é23456789abcdef0123456789abcdef01234567";
+
+    for hash in ["", "a", "../../../../etc/passwd", short, multibyte] {
+      assert!(store.object(hash).is_err(), "{}", hash);
+    }
+  }
+
+  #[test]
+  fn rollback_restores_and_removes_files() {
+    let (dir, mp, store) = instance("roundtrip");
+    let game_dir = mp.path();
+    let snapshot = take(&mp, &store, Some(&dir.join("first.json")), false).unwrap().unwrap();
+
+    assert_eq!(snapshot.state.files.keys().collect::<Vec<_>>(), ["config/sub/b.toml", "mods/a.jar", "profile.json"]);
+
+    std::fs::write(game_dir.join("mods/a.jar"), "changed").unwrap();
+    std::fs::write(game_dir.join("mods/extra.jar"), "extra").unwrap();
+    std::fs::remove_file(game_dir.join("config/sub/b.toml")).unwrap();
+
+    let snapshot = find("unused", Some(dir.join("first.json").to_str().unwrap())).unwrap();
+
+    assert_eq!(rollback(&mp, &store, &snapshot).unwrap(), (2, 1));
+    assert_eq!(std::fs::read_to_string(game_dir.join("mods/a.jar")).unwrap(), "a");
+    assert_eq!(std::fs::read_to_string(game_dir.join("config/sub/b.toml")).unwrap(), "b");
+    assert!(!game_dir.join("mods/extra.jar").exists());
+
+    // The state before the rollback was snapshotted, so it can be undone
+    let before = list(&snapshot.state.name);
+
+    assert_eq!(before.len(), 1);
+    assert!(before[0].state.files.contains_key("mods/extra.jar"));
+
+    finish(dir, &mp);
+  }
+
+  #[test]
+  fn malformed_snapshots_are_rejected() {
+    let (dir, mp, store) = instance("malformed");
+    let snapshot = take(&mp, &store, Some(&dir.join("first.json")), false).unwrap().unwrap();
+    let hash = snapshot.state.files["mods/a.jar"].clone();
+
+    for (file, hash) in [
+      ("../escaped.jar", hash.as_str()),
+      ("mods/../../escaped.jar", hash.as_str()),
+      ("/tmp/escaped.jar", hash.as_str()),
+      ("", hash.as_str()),
+      ("mods/b.jar", "../../a"),
+      ("mods/b.jar", "é"),
+    ] {
+      let mut malformed = snapshot.clone();
+
+      malformed.state.files.insert(file.to_string(), hash.to_string());
+
+      assert!(rollback(&mp, &store, &malformed).is_err(), "{} {}", file, hash);
+    }
+
+    assert!(!dir.join("escaped.jar").exists());
+    assert!(list(&snapshot.state.name).is_empty(), "snapshotted before rejecting the snapshot");
+
+    finish(dir, &mp);
+  }
+}
@@ -0,0 +1,347 @@
+use std::fmt::Formatter;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::directories::app_data_dirs;
+use crate::link::{CFMinecraftInstance, Result};
+
+/// Mod loader of an instance along with its version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModLoader {
+  Forge(String),
+  NeoForge(String),
+  Fabric(String),
+  Quilt(String),
+  Vanilla,
+}
+
+impl ModLoader {
+  /// Parses loader names used by the different launchers (`forge`, `Fabric`, `neoforge`, ...)
+  pub fn from_name(name: &str, version: String) -> Self {
+    match name.to_lowercase().as_str() {
+      "forge" | "minecraftforge" => ModLoader::Forge(version),
+      "neoforge" | "neoforged" => ModLoader::NeoForge(version),
+      "fabric" | "fabricloader" => ModLoader::Fabric(version),
+      "quilt" | "quiltloader" => ModLoader::Quilt(version),
+      _ => ModLoader::Vanilla,
+    }
+  }
+
+  /// Parses `<loader>-<version>` with an optional leading Minecraft version, e.g. `1.20.1-forge-47.1.0`
+  pub fn from_id(id: &str) -> Self {
+    let parts = id.split('-').collect::<Vec<_>>();
+
+    parts.iter()
+      .position(|it| ModLoader::from_name(it, String::new()) != ModLoader::Vanilla)
+      .map(|index| ModLoader::from_name(parts[index], parts[index + 1..].join("-")))
+      .unwrap_or(ModLoader::Vanilla)
+  }
+
+  /// MultiMC component (`cachedName`, `uid`) for this loader in `mmc-pack.json`
+  pub fn component(&self) -> Option<(&'static str, &'static str)> {
+    match self {
+      ModLoader::Forge(_) => Some(("Forge", "net.minecraftforge")),
+      ModLoader::NeoForge(_) => Some(("NeoForge", "net.neoforged")),
+      ModLoader::Fabric(_) => Some(("Fabric Loader", "net.fabricmc.fabric-loader")),
+      ModLoader::Quilt(_) => Some(("Quilt Loader", "org.quiltmc.quilt-loader")),
+      ModLoader::Vanilla => None,
+    }
+  }
+
+  /// Inverse of [`ModLoader::component`], `None` for components that aren't loaders
+  pub fn from_component(uid: &str, version: String) -> Option<Self> {
+    match uid {
+      "net.minecraftforge" => Some(ModLoader::Forge(version)),
+      "net.neoforged" => Some(ModLoader::NeoForge(version)),
+      "net.fabricmc.fabric-loader" => Some(ModLoader::Fabric(version)),
+      "org.quiltmc.quilt-loader" => Some(ModLoader::Quilt(version)),
+      _ => None,
+    }
+  }
+
+  /// `<loader>-<version>` as used in CurseForge pack manifests, inverse of [`ModLoader::from_id`]
+  pub fn id(&self) -> Option<String> {
+    let name = match self {
+      ModLoader::Forge(_) => "forge",
+      ModLoader::NeoForge(_) => "neoforge",
+      ModLoader::Fabric(_) => "fabric",
+      ModLoader::Quilt(_) => "quilt",
+      ModLoader::Vanilla => return None,
+    };
+
+    Some(format!("{}-{}", name, self.version()?))
+  }
+
+  pub fn version(&self) -> Option<&str> {
+    match self {
+      ModLoader::Forge(version)
+      | ModLoader::NeoForge(version)
+      | ModLoader::Fabric(version)
+      | ModLoader::Quilt(version) => Some(version),
+      ModLoader::Vanilla => None,
+    }
+  }
+}
+
+/// Pack the instance was installed from, written as `ManagedPack*` keys for launchers that support them
+#[derive(Debug, Clone)]
+pub struct ManagedPack {
+  /// `flame` for CurseForge, `modrinth` for Modrinth
+  pub kind: &'static str,
+  pub id: String,
+  pub name: String,
+  pub version_id: String,
+  pub version_name: String,
+}
+
+/// What every source launcher has to provide about an instance
+#[derive(Debug, Clone)]
+pub struct SourceInstance {
+  pub name: String,
+  pub mc_version: String,
+  pub loader: ModLoader,
+  /// Directory containing `mods`, `config`, `saves`, ... this is what gets linked
+  pub game_dir: PathBuf,
+  pub icon: Option<PathBuf>,
+  pub managed: Option<ManagedPack>,
+}
+
+/// A launcher instances can be linked from
+pub trait SourceLauncher {
+  fn name(&self) -> &'static str;
+
+  /// Directories instances are usually kept in
+  fn default_dirs(&self) -> Vec<PathBuf>;
+
+  /// File inside an instance directory that marks it as an instance of this launcher
+  fn instance_file(&self) -> &'static str;
+
+  /// Instance directories inside `root`
+  fn discover(&self, root: &Path) -> Vec<PathBuf> {
+    root.read_dir()
+      .map(|it| it.flatten()
+        .map(|it| it.path())
+        .filter(|it| it.join(self.instance_file()).is_file())
+        .collect()
+      )
+      .unwrap_or_default()
+  }
+
+  fn read(&self, dir: &Path) -> Result<SourceInstance>;
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: PathBuf) -> Result<T> {
+  Ok(serde_json::from_reader(File::open(path)?)?)
+}
+
+pub struct CurseForge;
+pub struct ATLauncher;
+pub struct ModrinthApp;
+pub struct FTBApp;
+
+impl SourceLauncher for CurseForge {
+  fn name(&self) -> &'static str { "CurseForge" }
+
+  fn default_dirs(&self) -> Vec<PathBuf> {
+    crate::detect::curseforge_candidates().into_iter().map(|it| it.path).collect()
+  }
+
+  fn instance_file(&self) -> &'static str { "minecraftinstance.json" }
+
+  fn read(&self, dir: &Path) -> Result<SourceInstance> {
+    let instance = read_json::<CFMinecraftInstance>(dir.join(self.instance_file()))?;
+    let managed = (instance.project_id != 0).then(|| ManagedPack {
+      kind: "flame",
+      id: instance.project_id.to_string(),
+      name: instance.name.clone(),
+      version_id: instance.file_id.to_string(),
+      version_name: instance.manifest.as_ref().map(|it| it.version.clone()).unwrap_or_default(),
+    });
+
+    Ok(SourceInstance {
+      mc_version: instance.loader.mc_version.clone(),
+      loader: instance.loader.version(),
+      game_dir: dir.to_path_buf(),
+      icon: instance.host_profile_image_path(dir),
+      managed,
+      name: instance.name,
+    })
+  }
+}
+
+#[derive(Deserialize)]
+struct ATLInstance {
+  /// Minecraft version, ATLauncher keeps the Mojang version json layout
+  id: String,
+  launcher: ATLLauncher,
+}
+
+#[derive(Deserialize)]
+struct ATLLauncher {
+  name: String,
+  #[serde(rename = "loaderVersion")] loader_version: Option<ATLLoaderVersion>,
+}
+
+#[derive(Deserialize)]
+struct ATLLoaderVersion {
+  version: String,
+  #[serde(rename = "type")] kind: String,
+}
+
+impl SourceLauncher for ATLauncher {
+  fn name(&self) -> &'static str { "ATLauncher" }
+
+  fn default_dirs(&self) -> Vec<PathBuf> {
+    let mut dirs = app_data_dirs("ATLauncher", None);
+
+    // The Flatpak keeps its data directly in the data directory
+    #[cfg(target_os = "linux")] dirs.push(crate::directories::flatpak_data_dir("com.atlauncher.ATLauncher"));
+
+    dirs.into_iter().map(|it| it.join("instances")).collect()
+  }
+
+  fn instance_file(&self) -> &'static str { "instance.json" }
+
+  fn read(&self, dir: &Path) -> Result<SourceInstance> {
+    let instance = read_json::<ATLInstance>(dir.join(self.instance_file()))?;
+    let loader = instance.launcher.loader_version
+      .map(|it| ModLoader::from_name(&it.kind, it.version))
+      .unwrap_or(ModLoader::Vanilla);
+
+    Ok(SourceInstance {
+      name: instance.launcher.name,
+      mc_version: instance.id,
+      loader,
+      game_dir: dir.to_path_buf(),
+      icon: Some(dir.join("instance.png")).filter(|it| it.is_file()),
+      managed: None,
+    })
+  }
+}
+
+#[derive(Deserialize)]
+struct ModrinthProfile {
+  metadata: ModrinthMetadata,
+}
+
+#[derive(Deserialize)]
+struct ModrinthMetadata {
+  name: String,
+  game_version: String,
+  loader: String,
+  loader_version: Option<ModrinthLoaderVersion>,
+  icon: Option<String>,
+  linked_data: Option<ModrinthLinkedData>,
+}
+
+#[derive(Deserialize)]
+struct ModrinthLoaderVersion {
+  id: String,
+}
+
+#[derive(Deserialize)]
+struct ModrinthLinkedData {
+  project_id: Option<String>,
+  version_id: Option<String>,
+}
+
+impl SourceLauncher for ModrinthApp {
+  fn name(&self) -> &'static str { "Modrinth App" }
+
+  fn default_dirs(&self) -> Vec<PathBuf> {
+    ["com.modrinth.theseus", "ModrinthApp"]
+      .iter()
+      .flat_map(|it| app_data_dirs(it, None))
+      .map(|it| it.join("profiles"))
+      .collect()
+  }
+
+  fn instance_file(&self) -> &'static str { "profile.json" }
+
+  fn read(&self, dir: &Path) -> Result<SourceInstance> {
+    let metadata = read_json::<ModrinthProfile>(dir.join(self.instance_file()))?.metadata;
+    let version = metadata.loader_version.map(|it| it.id).unwrap_or_default();
+    let managed = metadata.linked_data
+      .and_then(|it| Some((it.project_id?, it.version_id.unwrap_or_default())))
+      .map(|(id, version_id)| ManagedPack {
+        kind: "modrinth",
+        id,
+        name: metadata.name.clone(),
+        version_name: version_id.clone(),
+        version_id,
+      });
+
+    Ok(SourceInstance {
+      loader: ModLoader::from_name(&metadata.loader, version),
+      mc_version: metadata.game_version,
+      game_dir: dir.to_path_buf(),
+      icon: metadata.icon.map(PathBuf::from).filter(|it| it.is_file()),
+      managed,
+      name: metadata.name,
+    })
+  }
+}
+
+#[derive(Deserialize)]
+struct FTBInstance {
+  name: String,
+  #[serde(rename = "mcVersion")] mc_version: String,
+  #[serde(rename = "modLoader", default)] mod_loader: String,
+}
+
+impl SourceLauncher for FTBApp {
+  fn name(&self) -> &'static str { "FTB App" }
+
+  fn default_dirs(&self) -> Vec<PathBuf> {
+    #[cfg(windows)] let root = PathBuf::from(std::env::var("LOCALAPPDATA").unwrap_or_default()).join(".ftba");
+    #[cfg(target_os = "macos")] let root = crate::directories::home().join("Library/Application Support/.ftba");
+    #[cfg(target_os = "linux")] let root = crate::directories::home().join(".ftba");
+
+    vec![root.join("instances")]
+  }
+
+  fn instance_file(&self) -> &'static str { "instance.json" }
+
+  fn read(&self, dir: &Path) -> Result<SourceInstance> {
+    let instance = read_json::<FTBInstance>(dir.join(self.instance_file()))?;
+
+    Ok(SourceInstance {
+      name: instance.name,
+      loader: ModLoader::from_id(&instance.mod_loader),
+      mc_version: instance.mc_version,
+      game_dir: dir.to_path_buf(),
+      icon: Some(dir.join("folder.jpg")).filter(|it| it.is_file()),
+      managed: None,
+    })
+  }
+}
+
+/// Selectable source launcher, stored in the settings
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+pub enum Source {
+  #[default] CurseForge,
+  ATLauncher,
+  Modrinth,
+  Ftb,
+}
+
+impl Source {
+  pub const ALL: [Source; 4] = [Source::CurseForge, Source::ATLauncher, Source::Modrinth, Source::Ftb];
+
+  pub fn launcher(&self) -> &'static dyn SourceLauncher {
+    match self {
+      Source::CurseForge => &CurseForge,
+      Source::ATLauncher => &ATLauncher,
+      Source::Modrinth => &ModrinthApp,
+      Source::Ftb => &FTBApp,
+    }
+  }
+}
+
+impl std::fmt::Display for Source {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.launcher().name())
+  }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::export::relative_path;
+use crate::hash::sha1;
+use crate::inventory::{inventory, jar_path};
+use crate::jar::read_jar;
+use crate::link::Result;
+use crate::modpack::ModPack;
+
+/// Directories whose files count as configuration when comparing instances
+pub const CONFIG_DIRS: [&str; 2] = ["config", "defaultconfigs"];
+
+/// What an instance looked like at some point, enough to tell what changed later on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceState {
+  pub name: String,
+  pub mc_version: String,
+  pub loader: String,
+  /// Seconds since the unix epoch
+  pub created: u64,
+  pub mods: Vec<ModState>,
+  /// Config files relative to the game directory, mapped to their sha1
+  pub configs: BTreeMap<String, String>,
+  /// Files saved in the snapshot store, relative to the game directory and mapped to their sha1.
+  /// Empty for states that were only captured for comparing
+  #[serde(default)] pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModState {
+  /// Identifies the mod across versions, see [`ModState::key`]
+  pub key: String,
+  pub name: String,
+  pub version: String,
+  pub file_name: String,
+  #[serde(default)] pub project_id: Option<u64>,
+  #[serde(default)] pub file_id: Option<u64>,
+}
+
+impl ModState {
+  /// CurseForge project ID when known, the mod ID from the jar otherwise, the file name as a last resort
+  fn key(project_id: Option<u64>, mod_id: Option<&str>, file_name: &str) -> String {
+    match (project_id, mod_id) {
+      (Some(project_id), _) => format!("curseforge:{}", project_id),
+      (None, Some(mod_id)) if !mod_id.is_empty() => format!("mod:{}", mod_id),
+      _ => format!("file:{}", file_name),
+    }
+  }
+}
+
+pub fn now() -> u64 {
+  SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|it| it.as_secs()).unwrap_or_default()
+}
+
+impl InstanceState {
+  pub fn capture(mp: &ModPack) -> Result<InstanceState> {
+    let instance = mp.instance()?;
+    let mods_dir = instance.game_dir.join("mods");
+
+    let mods = inventory(mp)?
+      .into_iter()
+      .filter(|it| !it.missing)
+      .map(|entry| {
+        let jar_mod = read_jar(&jar_path(&mods_dir, &entry.file_name)).unwrap_or_default().into_iter().next();
+        let version = jar_mod.as_ref()
+          .map(|it| it.version.clone())
+          .filter(|it| !it.is_empty() && !it.starts_with('$'))
+          .unwrap_or_default();
+
+        ModState {
+          key: ModState::key(entry.project_id, jar_mod.as_ref().map(|it| it.id.as_str()), &entry.file_name),
+          name: entry.name,
+          version,
+          file_name: entry.file_name,
+          project_id: entry.project_id,
+          file_id: entry.file_id,
+        }
+      })
+      .collect();
+
+    let mut configs = BTreeMap::new();
+
+    for dir in CONFIG_DIRS {
+      for entry in WalkDir::new(instance.game_dir.join(dir)).follow_links(true) {
+        let entry = match entry {
+          Ok(entry) if entry.file_type().is_file() => entry,
+          _ => continue,
+        };
+
+        configs.insert(relative_path(&instance.game_dir, entry.path())?, sha1(entry.path())?);
+      }
+    }
+
+    Ok(InstanceState {
+      name: instance.name,
+      mc_version: instance.mc_version,
+      loader: instance.loader.id().unwrap_or_else(|| String::from("vanilla")),
+      created: now(),
+      mods,
+      configs,
+      files: BTreeMap::new(),
+    })
+  }
+
+  pub fn read(path: &Path) -> Result<InstanceState> {
+    Ok(serde_json::from_reader(File::open(path)?)?)
+  }
+
+  pub fn write(&self, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+      create_dir_all(parent)?;
+    }
+
+    serde_json::to_writer_pretty(File::create(path)?, self)?;
+
+    Ok(())
+  }
+}
+
+/// Where this application keeps its data, next to where confy keeps the settings
+pub fn data_dir() -> PathBuf {
+  directories::ProjectDirs::from("rs", "", crate::NAME)
+    .map(|it| it.data_dir().to_path_buf())
+    .unwrap_or_default()
+}
+
+/// `name` with everything that isn't safe in a directory name replaced by `_`
+pub fn dir_name(name: &str) -> String {
+  name
+    .chars()
+    .map(|it| if it.is_alphanumeric() || it == '-' || it == '_' { it } else { '_' })
+    .collect()
+}
+
+/// Where snapshots of the instance called `name` are kept by default
+pub fn snapshots_dir(name: &str) -> PathBuf {
+  data_dir().join("snapshots").join(dir_name(name))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use zip::write::FileOptions;
+  use zip::ZipWriter;
+
+  use crate::source::Source;
+
+  use super::*;
+
+  fn write_jar(path: &Path, id: &str, version: &str) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+
+    zip.start_file("fabric.mod.json", FileOptions::default()).unwrap();
+    write!(zip, r#"{{ "id": "{}", "version": "{}" }}"#, id, version).unwrap();
+    zip.finish().unwrap();
+  }
+
+  #[test]
+  fn capture_reads_disabled_jars_and_configs() {
+    let dir = std::env::temp_dir().join(format!("state-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    create_dir_all(dir.join("mods")).unwrap();
+    create_dir_all(dir.join("config/sub")).unwrap();
+    write_jar(&dir.join("mods/sodium.jar.disabled"), "sodium", "0.5.3");
+    write_jar(&dir.join("mods/lithium.jar"), "lithium", "0.11.2");
+    write_jar(&dir.join("mods/placeholder.jar"), "", "${version}");
+    std::fs::write(dir.join("config/sub/a.toml"), "a").unwrap();
+
+    std::fs::write(dir.join("minecraftinstance.json"), serde_json::json!({
+      "name": "Pack",
+      "baseModLoader": { "name": "fabric-0.14.21-1.20.1", "forgeVersion": "0.14.21", "minecraftVersion": "1.20.1" },
+      "installedAddons": [
+        { "addonID": 394468, "name": "Sodium", "installedFile": { "id": 7, "fileName": "sodium.jar" } }
+      ]
+    }).to_string()).unwrap();
+
+    let state = InstanceState::capture(&ModPack::new(dir.clone(), Source::CurseForge)).unwrap();
+    let mods = state.mods.iter()
+      .map(|it| (it.key.as_str(), it.version.as_str(), it.file_name.as_str()))
+      .collect::<Vec<_>>();
+
+    assert_eq!(state.mc_version, "1.20.1");
+    assert_eq!(mods, [
+      ("mod:lithium", "0.11.2", "lithium.jar"),
+      ("file:placeholder.jar", "", "placeholder.jar"),
+      ("curseforge:394468", "0.5.3", "sodium.jar"),
+    ]);
+    assert_eq!(state.configs.keys().collect::<Vec<_>>(), ["config/sub/a.toml"]);
+
+    let path = dir.join("states/state.json");
+
+    state.write(&path).unwrap();
+    assert_eq!(InstanceState::read(&path).unwrap().mods, state.mods);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn dir_names_only_keep_safe_characters() {
+    assert_eq!(dir_name("All the Mods 9"), "All_the_Mods_9");
+    assert_eq!(dir_name("../Pack-1_b"), "___Pack-1_b");
+  }
+}
@@ -0,0 +1,199 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use zip::ZipArchive;
+
+use crate::export::ADDON_DIRS;
+use crate::hash::cf_fingerprint;
+use crate::link::{get_cf_instance, Result};
+use crate::modpack::ModPack;
+use crate::source::Source;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+  Ok,
+  /// Fingerprint differs but the jar still opens
+  Modified,
+  /// Fingerprint differs and the jar can't be read
+  Corrupt,
+  /// CurseForge installed it but it's gone
+  Missing,
+  /// In `mods` but CurseForge didn't install it
+  Extra,
+  /// CurseForge recorded no fingerprint to compare with
+  Unverified,
+}
+
+impl FileStatus {
+  /// Whether the file needs attention, unverified files may well be fine
+  pub fn is_problem(self) -> bool {
+    !matches!(self, FileStatus::Ok | FileStatus::Unverified)
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileCheck {
+  /// Relative to the game directory
+  pub path: String,
+  pub status: FileStatus,
+  #[serde(skip_serializing_if = "Option::is_none")] pub project_id: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub expected: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub actual: Option<u32>,
+}
+
+fn is_readable_jar(path: &std::path::Path) -> bool {
+  File::open(path).ok().and_then(|it| ZipArchive::new(it).ok()).is_some()
+}
+
+/// Compares every addon file of `mp` with the fingerprint CurseForge recorded when installing it,
+/// jars in `mods` CurseForge doesn't know about are reported as extra
+pub fn verify(mp: &ModPack) -> Result<Vec<FileCheck>> {
+  if mp.source != Source::CurseForge {
+    return Err(Box::from("Only CurseForge records fingerprints of installed files"));
+  }
+
+  let game_dir = mp.instance()?.game_dir;
+  let addons = get_cf_instance(mp)?.installed_addons;
+  let mut checks = Vec::new();
+  let mut known = Vec::<PathBuf>::new();
+
+  for addon in &addons {
+    let file = match &addon.installed_file {
+      Some(file) => file,
+      None => continue,
+    };
+
+    let dir = addon.dir();
+    let dirs = match &dir {
+      Some(dir) => vec![dir.as_str()],
+      None => ADDON_DIRS.to_vec(),
+    };
+
+    let found = dirs.iter()
+      .flat_map(|dir| [
+        format!("{}/{}", dir, file.file_name),
+        format!("{}/{}.disabled", dir, file.file_name),
+      ])
+      .find(|it| game_dir.join(it).is_file());
+
+    let path = match found {
+      Some(path) => path,
+      None => {
+        checks.push(FileCheck {
+          path: format!("{}/{}", dir.as_deref().unwrap_or("mods"), file.file_name),
+          status: FileStatus::Missing,
+          project_id: Some(addon.addon_id),
+          expected: Some(file.fingerprint),
+          actual: None,
+        });
+        continue;
+      }
+    };
+
+    let full_path = game_dir.join(&path);
+    let actual = cf_fingerprint(&full_path)?;
+    let status = match actual == file.fingerprint {
+      _ if file.fingerprint == 0 => FileStatus::Unverified,
+      true => FileStatus::Ok,
+      false if file.file_name.ends_with(".jar") && !is_readable_jar(&full_path) => FileStatus::Corrupt,
+      false => FileStatus::Modified,
+    };
+
+    known.push(full_path);
+    checks.push(FileCheck {
+      path,
+      status,
+      project_id: Some(addon.addon_id),
+      expected: Some(file.fingerprint),
+      actual: Some(actual),
+    });
+  }
+
+  let mut extra = game_dir.join("mods").read_dir()
+    .map(|it| it.flatten().map(|it| it.path()).collect::<Vec<_>>())
+    .unwrap_or_default();
+
+  extra.sort();
+
+  for path in extra {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let is_jar = name.ends_with(".jar") || name.ends_with(".jar.disabled");
+
+    if path.is_file() && is_jar && !known.contains(&path) {
+      checks.push(FileCheck {
+        path: format!("mods/{}", name),
+        status: FileStatus::Extra,
+        project_id: None,
+        expected: None,
+        actual: Some(cf_fingerprint(&path)?),
+      });
+    }
+  }
+
+  Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn statuses_and_addon_folders() {
+    let dir = std::env::temp_dir().join(format!("verify-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    for folder in ["mods", "resourcepacks", "shaderpacks"] {
+      std::fs::create_dir_all(dir.join(folder)).unwrap();
+    }
+
+    std::fs::write(dir.join("mods/ok.jar"), "ok").unwrap();
+    std::fs::write(dir.join("mods/changed.jar"), "changed").unwrap();
+    std::fs::write(dir.join("mods/unknown.jar"), "unknown").unwrap();
+    std::fs::write(dir.join("mods/extra.jar"), "extra").unwrap();
+    std::fs::write(dir.join("resourcepacks/pack.zip"), "pack").unwrap();
+
+    let fingerprint = |path: &str| cf_fingerprint(&dir.join(path)).unwrap();
+    let addon = |id: u64, name: &str, fingerprint: u32, extra: serde_json::Value| {
+      let mut addon = serde_json::json!({
+        "addonID": id,
+        "installedFile": { "id": id, "fileName": name, "packageFingerprint": fingerprint }
+      });
+
+      addon.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+      addon
+    };
+
+    std::fs::write(dir.join("minecraftinstance.json"), serde_json::json!({
+      "name": "Pack",
+      "baseModLoader": { "name": "forge-47.1.0", "forgeVersion": "47.1.0", "minecraftVersion": "1.20.1" },
+      "installedAddons": [
+        addon(1, "ok.jar", fingerprint("mods/ok.jar"), serde_json::json!({})),
+        addon(2, "changed.jar", 1, serde_json::json!({ "categorySection": { "path": "mods" } })),
+        addon(3, "unknown.jar", 0, serde_json::json!({})),
+        addon(4, "pack.zip", fingerprint("resourcepacks/pack.zip"), serde_json::json!({ "classId": 12 })),
+        addon(5, "shader.zip", 7, serde_json::json!({ "categorySection": { "path": "shaderpacks" } })),
+        addon(6, "gone.zip", 7, serde_json::json!({ "classId": 12 })),
+      ]
+    }).to_string()).unwrap();
+
+    let checks = verify(&ModPack::new(dir.clone(), Source::CurseForge)).unwrap();
+    let statuses = checks.iter().map(|it| (it.path.as_str(), it.status)).collect::<Vec<_>>();
+
+    assert_eq!(statuses, [
+      ("mods/ok.jar", FileStatus::Ok),
+      ("mods/changed.jar", FileStatus::Corrupt),
+      ("mods/unknown.jar", FileStatus::Unverified),
+      ("resourcepacks/pack.zip", FileStatus::Ok),
+      ("shaderpacks/shader.zip", FileStatus::Missing),
+      ("resourcepacks/gone.zip", FileStatus::Missing),
+      ("mods/extra.jar", FileStatus::Extra),
+    ]);
+
+    assert!(!FileStatus::Unverified.is_problem());
+    assert!(FileStatus::Missing.is_problem());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Ordering;
+
+/// Compares a single part, numerically where both parts are numbers
+fn compare_part(a: &str, b: &str) -> Ordering {
+  match (a.parse::<u64>(), b.parse::<u64>()) {
+    (Ok(a), Ok(b)) => a.cmp(&b),
+    _ => a.cmp(b),
+  }
+}
+
+/// Splits off the prerelease after the first `-` and drops build metadata after `+`
+fn split_prerelease(version: &str) -> (&str, Option<&str>) {
+  let version = version.split('+').next().unwrap_or_default();
+
+  match version.split_once('-') {
+    Some((release, prerelease)) => (release, Some(prerelease)),
+    None => (version, None),
+  }
+}
+
+/// Compares prereleases the way semver does, identifier by identifier where numbers sort before text
+/// and a shorter prerelease sorts first when everything else is equal
+fn compare_prerelease(a: &str, b: &str) -> Ordering {
+  let (a, b) = (a.split('.').collect::<Vec<_>>(), b.split('.').collect::<Vec<_>>());
+
+  for (a, b) in a.iter().zip(&b) {
+    let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+      (Ok(a), Ok(b)) => a.cmp(&b),
+      (Ok(_), Err(_)) => Ordering::Less,
+      (Err(_), Ok(_)) => Ordering::Greater,
+      _ => a.cmp(b),
+    };
+
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+
+  a.len().cmp(&b.len())
+}
+
+/// Compares versions part by part, numerically where both parts are numbers. Missing parts count as 0,
+/// so `1.20` equals `1.20.0`. A prerelease (`1.20.1-rc.1`) sorts before its release and build metadata
+/// (`+build.5`) is ignored, like semver
+pub fn compare(a: &str, b: &str) -> Ordering {
+  let ((a, a_pre), (b, b_pre)) = (split_prerelease(a), split_prerelease(b));
+  let (a, b) = (a.split('.').collect::<Vec<_>>(), b.split('.').collect::<Vec<_>>());
+
+  for index in 0..a.len().max(b.len()) {
+    let ordering = compare_part(a.get(index).unwrap_or(&"0"), b.get(index).unwrap_or(&"0"));
+
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+
+  match (a_pre, b_pre) {
+    (Some(a), Some(b)) => compare_prerelease(a, b),
+    (Some(_), None) => Ordering::Less,
+    (None, Some(_)) => Ordering::Greater,
+    (None, None) => Ordering::Equal,
+  }
+}
+
+/// `version` with its last given part bumped, `1.20.1` becomes `1.21` and `1` becomes `2`
+fn next(version: &str, parts: usize) -> String {
+  let mut kept = split_prerelease(version).0.split('.').take(parts).map(str::to_string).collect::<Vec<_>>();
+
+  if let Some(last) = kept.last_mut() {
+    *last = (last.parse::<u64>().unwrap_or(0) + 1).to_string();
+  }
+
+  kept.join(".")
+}
+
+/// A single Fabric style predicate like `>=1.20`, `~1.20.1`, `1.20.x` or `*`
+fn matches_predicate(version: &str, predicate: &str) -> bool {
+  let predicate = predicate.trim();
+
+  for (op, accepts) in [
+    (">=", &[Ordering::Greater, Ordering::Equal][..]),
+    ("<=", &[Ordering::Less, Ordering::Equal][..]),
+    (">", &[Ordering::Greater][..]),
+    ("<", &[Ordering::Less][..]),
+    ("=", &[Ordering::Equal][..]),
+  ] {
+    if let Some(other) = predicate.strip_prefix(op) {
+      return accepts.contains(&compare(version, other.trim()));
+    }
+  }
+
+  if let Some(other) = predicate.strip_prefix('~') {
+    let parts = split_prerelease(other).0.split('.').count().clamp(1, 2);
+    return compare(version, other) != Ordering::Less && compare(version, &next(other, parts)) == Ordering::Less;
+  }
+
+  // Like semver, the first non-zero part can't change, so `^0.4` means `<0.5` and `^0.0.3` means `<0.0.4`
+  if let Some(other) = predicate.strip_prefix('^') {
+    let given = split_prerelease(other).0.split('.').collect::<Vec<_>>();
+    let parts = given.iter()
+      .position(|it| it.parse::<u64>().map(|it| it != 0).unwrap_or(true))
+      .map(|it| it + 1)
+      .unwrap_or(given.len());
+
+    return compare(version, other) != Ordering::Less && compare(version, &next(other, parts)) == Ordering::Less;
+  }
+
+  if predicate.is_empty() || predicate == "*" {
+    return true;
+  }
+
+  match predicate.find(['x', 'X', '*']) {
+    Some(index) => version.starts_with(&predicate[..index]),
+    None => compare(version, predicate) == Ordering::Equal,
+  }
+}
+
+/// A single Maven range like `[1.20,1.21)`, `[1.20.1]` or `(,1.19]`
+fn matches_maven(version: &str, range: &str) -> bool {
+  let inclusive_start = range.starts_with('[');
+  let inclusive_end = range.ends_with(']');
+  let inner = range.trim_start_matches(['[', '(']).trim_end_matches([']', ')']);
+
+  let (start, end) = match inner.split_once(',') {
+    Some((start, end)) => (start.trim(), end.trim()),
+    None => (inner.trim(), inner.trim()),
+  };
+
+  let after_start = start.is_empty() || match compare(version, start) {
+    Ordering::Greater => true,
+    Ordering::Equal => inclusive_start,
+    Ordering::Less => false,
+  };
+
+  let before_end = end.is_empty() || match compare(version, end) {
+    Ordering::Less => true,
+    Ordering::Equal => inclusive_end,
+    Ordering::Greater => false,
+  };
+
+  after_start && before_end
+}
+
+/// Whether `version` satisfies `range`. Forge and NeoForge use Maven ranges (`[1.20,1.21)`, unions separated
+/// by commas) where a bare version is only a recommendation and matches everything, Fabric and Quilt use
+/// predicates (`>=1.20 <1.21`, alternatives separated by `||`) where a bare version has to match exactly
+pub fn matches_range(version: &str, range: &str, maven: bool) -> bool {
+  let range = range.trim();
+
+  if !maven {
+    return range.split("||").any(|alternative| alternative.split_whitespace().all(|it| matches_predicate(version, it)));
+  }
+
+  if !range.starts_with('[') && !range.starts_with('(') {
+    return true;
+  }
+
+  let mut ranges = Vec::new();
+  let mut start = 0;
+
+  for (index, c) in range.char_indices() {
+    if c == ']' || c == ')' {
+      ranges.push(range[start..=index].trim_start_matches(',').trim());
+      start = index + 1;
+    }
+  }
+
+  ranges.iter().any(|it| matches_maven(version, it))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn prereleases_sort_before_their_release() {
+    assert!(matches_range("1.20.1", ">=1.20.1-rc.1", false));
+    assert!(matches_range("1.20.1", ">=1.20-alpha.23.w13.a", false));
+    assert!(matches_range("1.20.1-rc.1", ">=1.20.1-pre.2", false));
+    assert!(!matches_range("1.20.1-rc.1", ">=1.20.1", false));
+    assert!(matches_range("1.20.1-rc.1", "<1.20.1", false));
+    assert_eq!(compare("1.0.0-alpha", "1.0.0-alpha.1"), Ordering::Less);
+    assert_eq!(compare("1.0.0-alpha.1", "1.0.0-alpha.beta"), Ordering::Less);
+    assert_eq!(compare("1.0.0-rc.2", "1.0.0-rc.10"), Ordering::Less);
+    assert_eq!(compare("0.14.21+build.5", "0.14.21"), Ordering::Equal);
+  }
+
+  #[test]
+  fn fabric_predicates() {
+    assert!(matches_range("1.20.1", ">=1.20 <1.21", false));
+    assert!(!matches_range("1.21", ">=1.20 <1.21", false));
+    assert!(matches_range("1.20.1", "1.19.x || 1.20.x", false));
+    assert!(matches_range("1.20.4", "~1.20.1", false));
+    assert!(!matches_range("1.21", "~1.20.1", false));
+    assert!(matches_range("1.20.1", "~1.20.1-rc.1", false));
+    assert!(matches_range("1.20.1", "^1.19", false));
+    assert!(!matches_range("2.0", "^1.19", false));
+    assert!(matches_range("0.4.9", "^0.4", false));
+    assert!(!matches_range("0.5.0", "^0.4", false));
+    assert!(matches_range("0.0.3", "^0.0.3", false));
+    assert!(!matches_range("0.0.4", "^0.0.3", false));
+    assert!(!matches_range("1.0", "^0", false));
+    assert!(matches_range("1.20.1", "*", false));
+    assert!(matches_range("1.20", "1.20.0", false));
+    assert!(!matches_range("1.20.1", "1.20", false));
+  }
+
+  #[test]
+  fn maven_ranges() {
+    assert!(matches_range("1.20.1", "[1.20,1.21)", true));
+    assert!(!matches_range("1.21", "[1.20,1.21)", true));
+    assert!(matches_range("1.19.2", "(,1.19.2]", true));
+    assert!(matches_range("1.20.1", "[1.20.1]", true));
+    assert!(matches_range("1.20.1", "[1.18,1.19),[1.20,)", true));
+    assert!(!matches_range("1.19.1", "[1.18,1.19),[1.20,)", true));
+    assert!(matches_range("1.12.2", "1.20.1", true));
+  }
+}
@@ -0,0 +1,250 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::directories::home;
+
+/// A Wine prefix (anything with a `drive_c`) and what created it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prefix {
+  pub path: PathBuf,
+  pub kind: &'static str,
+}
+
+fn children(dir: &Path) -> Vec<PathBuf> {
+  dir.read_dir()
+    .map(|it| it.flatten().map(|it| it.path()).collect())
+    .unwrap_or_default()
+}
+
+/// `prefix:` entries from the Lutris game configs in `dir`, usually `~/.config/lutris/games`
+fn lutris_config_prefixes(dir: &Path) -> Vec<PathBuf> {
+  children(dir)
+    .into_iter()
+    .filter_map(|it| read_to_string(it).ok())
+    .flat_map(|it| {
+      it.lines()
+        .filter_map(|line| line.trim().strip_prefix("prefix:"))
+        .map(|it| PathBuf::from(it.trim().trim_matches(['"', '\''])))
+        .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+fn steam_roots() -> Vec<PathBuf> {
+  let home = home();
+
+  vec![
+    home.join(".steam/steam"),
+    home.join(".local/share/Steam"),
+    home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+  ]
+}
+
+/// Every Wine, Lutris, Bottles and Steam Proton prefix in the usual locations
+pub fn prefixes() -> Vec<Prefix> {
+  let home = home();
+  let mut found = Vec::new();
+  let mut push = |path: PathBuf, kind: &'static str| {
+    if path.join("drive_c").is_dir() && !found.iter().any(|it: &Prefix| it.path == path) {
+      found.push(Prefix { path, kind });
+    }
+  };
+
+  if let Ok(prefix) = std::env::var("WINEPREFIX") {
+    push(PathBuf::from(prefix), "WINEPREFIX");
+  }
+
+  push(home.join(".wine"), "Wine");
+  children(&home.join(".local/share/wineprefixes")).into_iter().for_each(|it| push(it, "Wine"));
+
+  lutris_config_prefixes(&home.join(".config/lutris/games")).into_iter().for_each(|it| push(it, "Lutris"));
+  children(&home.join("Games")).into_iter().for_each(|it| push(it, "Lutris"));
+
+  children(&home.join(".local/share/bottles/bottles")).into_iter().for_each(|it| push(it, "Bottles"));
+  children(&home.join(".var/app/com.usebottles.bottles/data/bottles/bottles"))
+    .into_iter()
+    .for_each(|it| push(it, "Bottles"));
+
+  for steam in steam_roots() {
+    children(&steam.join("steamapps/compatdata"))
+      .into_iter()
+      .for_each(|it| push(it.join("pfx"), "Steam Proton"));
+  }
+
+  found
+}
+
+/// CurseForge instance directories inside a prefix, one per Windows user
+pub fn curseforge_instance_dirs(prefix: &Path) -> Vec<PathBuf> {
+  children(&prefix.join("drive_c/users"))
+    .into_iter()
+    .flat_map(|user| [
+      user.join("curseforge/minecraft/Instances"),
+      user.join("Documents/curseforge/minecraft/Instances"),
+    ])
+    .filter(|it| it.is_dir())
+    .collect()
+}
+
+/// CurseForge app data directories inside a prefix, one per Windows user
+pub fn curseforge_settings_dirs(prefix: &Path) -> Vec<PathBuf> {
+  children(&prefix.join("drive_c/users"))
+    .into_iter()
+    .map(|user| user.join("AppData/Roaming/CurseForge"))
+    .filter(|it| it.is_dir())
+    .collect()
+}
+
+/// The closest ancestor of `path` that is a Wine prefix
+pub fn prefix_of(path: &Path) -> Option<PathBuf> {
+  path.ancestors()
+    .find(|it| it.join("drive_c").is_dir())
+    .map(Path::to_path_buf)
+}
+
+/// Whether `path` looks like `C:\...`
+pub fn is_windows_path(path: &str) -> bool {
+  let bytes = path.as_bytes();
+
+  bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// Translates a Windows path recorded inside a prefix (e.g. `C:\users\me\curseforge`) to a host path,
+/// drive letters are resolved through `dosdevices` and fall back to `drive_c` for `C:`
+pub fn to_host_path(prefix: &Path, path: &str) -> Option<PathBuf> {
+  if !is_windows_path(path) {
+    return None;
+  }
+
+  let drive = path[..1].to_ascii_lowercase();
+  let rest = path[2..]
+    .split(['\\', '/'])
+    .filter(|it| !it.is_empty())
+    .collect::<PathBuf>();
+
+  let device = prefix.join("dosdevices").join(format!("{}:", drive));
+  let root = if device.exists() {
+    device
+  } else if drive == "c" {
+    prefix.join("drive_c")
+  } else {
+    return None;
+  };
+
+  Some(root.join(rest))
+}
+
+/// Translates a host path inside `prefix` back to the Windows path programs in the prefix see, the inverse of
+/// `to_host_path`. `drive_c` wins over other drives, `dosdevices` links are matched by their target
+pub fn to_windows_path(prefix: &Path, path: &Path) -> Option<String> {
+  let canonical = prefix.canonicalize().ok()
+    .zip(path.strip_prefix(prefix).ok())
+    .map(|(prefix, rest)| prefix.join(rest));
+  let devices = children(&prefix.join("dosdevices"))
+    .into_iter()
+    .filter_map(|it| {
+      let name = it.file_name()?.to_str()?.to_ascii_lowercase();
+      let drive = name.strip_suffix(':').filter(|it| it.len() == 1)?.to_string();
+
+      Some((drive, it.canonicalize().ok()?))
+    });
+
+  let (drive, rest) = std::iter::once((String::from("c"), prefix.join("drive_c")))
+    .chain(devices)
+    .filter_map(|(drive, root)| {
+      let rest = path.strip_prefix(&root).ok().or_else(|| canonical.as_deref()?.strip_prefix(&root).ok())?;
+      Some((drive, rest.to_path_buf()))
+    })
+    .min_by_key(|(_, rest)| rest.components().count())?;
+
+  let rest = rest.components()
+    .map(|it| it.as_os_str().to_string_lossy().to_string())
+    .collect::<Vec<_>>();
+
+  Some(format!("{}:\\{}", drive.to_uppercase(), rest.join("\\")))
+}
+
+/// Translates `path` if it is inside a Wine prefix and we aren't on Windows, the inverse of `host_path`
+pub fn windows_path(path: &Path) -> Option<String> {
+  if cfg!(windows) {
+    return None;
+  }
+
+  to_windows_path(&prefix_of(path)?, path)
+}
+
+/// Translates `path` if it is a Windows path and we aren't on Windows,
+/// using the prefix `near` lives in
+pub fn host_path(path: &str, near: &Path) -> Option<PathBuf> {
+  if cfg!(not(windows)) && is_windows_path(path) {
+    to_host_path(&prefix_of(near)?, path)
+  } else {
+    Some(PathBuf::from(path))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::{create_dir_all, write};
+
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wine-{}-{}", std::process::id(), name));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn drives_resolve_through_dosdevices() {
+    let dir = temp_dir("dosdevices");
+    let (prefix, games) = (dir.join("prefix"), dir.join("games"));
+
+    create_dir_all(prefix.join("drive_c/users")).unwrap();
+    create_dir_all(prefix.join("dosdevices")).unwrap();
+    create_dir_all(&games).unwrap();
+    symlink::symlink_dir(&games, prefix.join("dosdevices/d:")).unwrap();
+
+    assert_eq!(to_host_path(&prefix, r"D:\curseforge\minecraft"), Some(prefix.join("dosdevices/d:/curseforge/minecraft")));
+    assert_eq!(to_host_path(&prefix, r"d:/x"), Some(prefix.join("dosdevices/d:/x")));
+    assert_eq!(to_host_path(&prefix, r"E:\x"), None);
+    assert_eq!(to_host_path(&prefix, "/home/me"), None);
+    assert_eq!(to_windows_path(&prefix, &games.join("curseforge/Instances")).as_deref(), Some(r"D:\curseforge\Instances"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn c_falls_back_to_drive_c() {
+    let dir = temp_dir("drive_c");
+
+    create_dir_all(dir.join("drive_c/users/me")).unwrap();
+
+    let host = to_host_path(&dir, r"C:\users\me\curseforge\").unwrap();
+
+    assert_eq!(host, dir.join("drive_c/users/me/curseforge"));
+    assert_eq!(prefix_of(&host), Some(dir.clone()));
+    assert_eq!(to_windows_path(&dir, &host).as_deref(), Some(r"C:\users\me\curseforge"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn lutris_prefixes_come_from_game_configs() {
+    let dir = temp_dir("lutris");
+
+    write(dir.join("curseforge.yml"), "game:\n  exe: CurseForge.exe\n  prefix: \"/home/me/Games/curseforge\"\n").unwrap();
+    write(dir.join("other.yml"), "game:\n  prefix: '/home/me/Games/other'\nwine:\n  version: lutris\n").unwrap();
+    write(dir.join("native.yml"), "game:\n  exe: /usr/bin/game\n").unwrap();
+
+    let mut prefixes = lutris_config_prefixes(&dir);
+    prefixes.sort();
+
+    assert_eq!(prefixes, [PathBuf::from("/home/me/Games/curseforge"), PathBuf::from("/home/me/Games/other")]);
+    assert!(lutris_config_prefixes(&dir.join("missing")).is_empty());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}